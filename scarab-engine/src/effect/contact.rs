@@ -0,0 +1,245 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use core::hash::Hasher;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::types::state_hash::StateHasher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The stage of a contact between two entities that an event reports
+pub enum ContactPhase {
+    /// The entities started touching this tick. Only sent once per contact
+    Enter,
+    /// The entities are still touching. Sent at the [ContactPolicy]'s interval while they touch
+    Stay,
+    /// The entities stopped touching this tick. Only sent once per contact
+    Exit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Reports a change in the contact between two entities.
+/// The entities are in the order the contact was reported to the [ContactTracker],
+/// which for a scene means `a` was registered before `b`
+pub struct ContactEvent {
+    /// The first entity in the contact
+    pub a: Uuid,
+    /// The second entity in the contact
+    pub b: Uuid,
+    /// What happened to the contact
+    pub phase: ContactPhase,
+}
+
+impl ContactEvent {
+    /// Whether the given entity is part of this contact
+    pub fn involves(&self, uuid: Uuid) -> bool {
+        self.a == uuid || self.b == uuid
+    }
+
+    /// The entity in the contact that isn't `uuid`, if `uuid` is part of the contact
+    pub fn other(&self, uuid: Uuid) -> Option<Uuid> {
+        if self.a == uuid {
+            Some(self.b)
+        } else if self.b == uuid {
+            Some(self.a)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Controls how often events are sent for entities that stay in contact
+pub struct ContactPolicy {
+    /// The game time (in seconds) between each [ContactPhase::Stay] event of a contact,
+    /// counted from when the contact started. At 0, stay events are sent every tick
+    pub stay_interval: f64,
+}
+
+impl Default for ContactPolicy {
+    fn default() -> Self {
+        Self { stay_interval: 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Tracks which entities are touching between ticks so that contact events are only sent when
+/// the contact changes, or when the [ContactPolicy] allows another stay event.
+/// Events are sent in the order the contacts were given to [ContactTracker::update],
+/// with exits first in the order they were last given
+pub struct ContactTracker {
+    /// The game time since the last event for each current contact
+    contacts: BTreeMap<(Uuid, Uuid), f64>,
+    /// The current contacts in the order they were last given
+    order: Vec<(Uuid, Uuid)>,
+    events: Vec<ContactEvent>,
+}
+
+impl ContactTracker {
+    /// Orders the pair of entities the same way no matter which way around they're given
+    fn key(a: Uuid, b: Uuid) -> (Uuid, Uuid) {
+        if a < b {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+
+    /// Replaces the events with those for the entities `touching` after `dt` seconds of game time
+    pub fn update<I: IntoIterator<Item = (Uuid, Uuid)>>(
+        &mut self,
+        touching: I,
+        dt: f64,
+        policy: &ContactPolicy,
+    ) {
+        self.events.clear();
+        let touching: Vec<(Uuid, Uuid)> = touching.into_iter().collect();
+        let touching_keys: BTreeSet<(Uuid, Uuid)> =
+            touching.iter().map(|&(a, b)| Self::key(a, b)).collect();
+
+        for &(a, b) in self.order.iter() {
+            if !touching_keys.contains(&Self::key(a, b)) {
+                self.contacts.remove(&Self::key(a, b));
+                self.events.push(ContactEvent {
+                    a,
+                    b,
+                    phase: ContactPhase::Exit,
+                });
+            }
+        }
+
+        self.order.clear();
+        let mut seen = BTreeSet::new();
+        for (a, b) in touching {
+            let key = Self::key(a, b);
+            if !seen.insert(key) {
+                continue;
+            }
+            self.order.push((a, b));
+
+            let phase = match self.contacts.get_mut(&key) {
+                None => {
+                    self.contacts.insert(key, 0.0);
+                    Some(ContactPhase::Enter)
+                }
+                Some(elapsed) => {
+                    *elapsed += dt;
+                    if *elapsed >= policy.stay_interval {
+                        // Keep the remainder so stays don't drift later with uneven ticks
+                        *elapsed = if policy.stay_interval > 0.0 {
+                            *elapsed - policy.stay_interval
+                        } else {
+                            0.0
+                        };
+                        Some(ContactPhase::Stay)
+                    } else {
+                        None
+                    }
+                }
+            };
+
+            if let Some(phase) = phase {
+                self.events.push(ContactEvent { a, b, phase });
+            }
+        }
+    }
+
+    /// The contact events from the latest update
+    pub fn events(&self) -> &[ContactEvent] {
+        &self.events
+    }
+
+    /// Whether the two entities were touching as of the latest update
+    pub fn is_touching(&self, a: Uuid, b: Uuid) -> bool {
+        self.contacts.contains_key(&Self::key(a, b))
+    }
+
+    /// Writes the current contacts and events to the hasher, with each entity written as its
+    /// position from `index_of` so that the hash doesn't depend on entity uuids
+    pub fn hash_state<F: Fn(Uuid) -> Option<usize>>(&self, hasher: &mut StateHasher, index_of: F) {
+        let write_entity = |hasher: &mut StateHasher, uuid: Uuid| {
+            hasher.write_usize(index_of(uuid).map_or(0, |i| i + 1));
+        };
+
+        hasher.write_usize(self.order.len());
+        for &(a, b) in self.order.iter() {
+            write_entity(hasher, a);
+            write_entity(hasher, b);
+            hasher.write_scalar(self.contacts[&Self::key(a, b)]);
+        }
+
+        hasher.write_usize(self.events.len());
+        for event in self.events.iter() {
+            write_entity(hasher, event.a);
+            write_entity(hasher, event.b);
+            hasher.write_u32(event.phase as u32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const NOT_TOUCHING: [(Uuid, Uuid); 0] = [];
+
+    fn phases(tracker: &ContactTracker) -> Vec<ContactPhase> {
+        tracker.events().iter().map(|e| e.phase).collect()
+    }
+
+    #[test]
+    fn enter_and_exit_fire_once_and_reentry_restarts_the_interval() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let policy = ContactPolicy { stay_interval: 1.0 };
+        let mut tracker = ContactTracker::default();
+
+        tracker.update([(a, b)], 0.5, &policy);
+        assert_eq!(phases(&tracker), vec![ContactPhase::Enter]);
+        tracker.update([(b, a)], 0.5, &policy);
+        assert_eq!(phases(&tracker), vec![]);
+        tracker.update([(a, b)], 0.5, &policy);
+        assert_eq!(phases(&tracker), vec![ContactPhase::Stay]);
+
+        tracker.update(NOT_TOUCHING, 0.5, &policy);
+        assert_eq!(phases(&tracker), vec![ContactPhase::Exit]);
+        tracker.update(NOT_TOUCHING, 0.5, &policy);
+        assert_eq!(phases(&tracker), vec![]);
+        assert!(!tracker.is_touching(a, b));
+
+        tracker.update([(a, b)], 0.5, &policy);
+        assert_eq!(phases(&tracker), vec![ContactPhase::Enter]);
+        tracker.update([(a, b)], 0.5, &policy);
+        assert_eq!(phases(&tracker), vec![]);
+    }
+
+    #[test]
+    fn events_follow_the_order_contacts_are_given() {
+        let mut ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        // Give the contacts against the uuid order to show it isn't used
+        ids.sort();
+        ids.reverse();
+        let (a, b, c, d) = (ids[0], ids[1], ids[2], ids[3]);
+        let policy = ContactPolicy::default();
+        let mut tracker = ContactTracker::default();
+
+        tracker.update([(a, b), (c, d), (a, c)], 0.1, &policy);
+        let pairs: Vec<(Uuid, Uuid)> = tracker.events().iter().map(|e| (e.a, e.b)).collect();
+        assert_eq!(pairs, vec![(a, b), (c, d), (a, c)]);
+
+        tracker.update([(a, c), (a, d)], 0.1, &policy);
+        let events: Vec<(Uuid, Uuid, ContactPhase)> = tracker
+            .events()
+            .iter()
+            .map(|e| (e.a, e.b, e.phase))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (a, b, ContactPhase::Exit),
+                (c, d, ContactPhase::Exit),
+                (a, c, ContactPhase::Stay),
+                (a, d, ContactPhase::Enter),
+            ]
+        );
+    }
+}
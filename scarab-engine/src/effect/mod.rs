@@ -7,6 +7,8 @@ use crate::{
     ScarabResult,
 };
 
+/// Events for entities touching each other
+pub mod contact;
 /// Helper structs for applying basic effects and attacks to entities
 #[cfg(feature = "effect-helpers")]
 pub mod effect_helpers;
@@ -1,3 +1,5 @@
+use core::hash::Hasher;
+
 use graphics::{
     types::{Color, Scalar},
     Context,
@@ -5,6 +7,7 @@ use graphics::{
 use opengl_graphics::GlGraphics;
 use piston::RenderArgs;
 use serde::{Deserialize, Serialize};
+use shapes::Point;
 use uuid::Uuid;
 
 use super::field::{Cell, Field};
@@ -13,10 +16,12 @@ use crate::{
     rendering::{registry::TextureRegistry, Camera, View},
     scene::GameTickArgs,
     types::{
+        material::{MaterialCombineRules, PhysicsMaterial},
         physbox::{HasBox, PhysBox},
-        HasSolidity, Health, Solidity, Velocity, SOLID,
+        state_hash::{StateHash, StateHasher},
+        Axis, BoxEdge, HasSolidity, Health, Solidity, Velocity, SOLID,
     },
-    HasBox, HasBoxMut, HasHealth, HasSolidity, HasUuid, PhysicsError, PhysicsResult, ScarabResult,
+    HasBoxMut, HasHealth, HasSolidity, HasUuid, PhysicsError, PhysicsResult, ScarabResult,
 };
 
 /// Handles the registration of entities (loading and unloading)
@@ -31,7 +36,76 @@ pub trait HasEntity {
     fn get_entity_mut(&mut self) -> &mut Entity;
 }
 
-#[derive(Debug, Serialize, Deserialize, HasBox, HasBoxMut, HasHealth, HasUuid, HasSolidity)]
+/// How often an entity is updated, in game ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "UpdateRateDef")]
+pub struct UpdateRate {
+    ticks_per_update: u32,
+}
+
+/// Deserializes through [UpdateRate::every] so saved rates of 0 are still treated as 1
+#[derive(Deserialize)]
+struct UpdateRateDef {
+    ticks_per_update: u32,
+}
+
+impl From<UpdateRateDef> for UpdateRate {
+    fn from(def: UpdateRateDef) -> Self {
+        Self::every(def.ticks_per_update)
+    }
+}
+
+impl UpdateRate {
+    /// Updates the entity once every `ticks` game ticks. A rate of 0 is treated as 1
+    pub fn every(ticks: u32) -> Self {
+        Self {
+            ticks_per_update: ticks.max(1),
+        }
+    }
+
+    /// The number of game ticks between each update
+    pub fn ticks_per_update(&self) -> u32 {
+        self.ticks_per_update
+    }
+}
+
+impl Default for UpdateRate {
+    fn default() -> Self {
+        Self::every(1)
+    }
+}
+
+/// Whether an entity is drawn when the scene renders. Visible entities are
+/// still culled by the camera when they're off screen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RenderVisibility {
+    /// The entity is drawn whenever the camera can see it
+    #[default]
+    Visible,
+    /// The entity is never drawn, but is still updated
+    Hidden,
+}
+
+/// Where an entity is drawn relative to other entities. Entities are drawn by increasing
+/// z layer, then within a layer by the bottom of their box in scenes that [y-sort](crate::scene::Scene::set_y_sort),
+/// so that entities lower on screen are drawn over those behind them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RenderOrder {
+    /// Entities on higher layers are always drawn over those on lower layers
+    pub z_layer: i32,
+    /// Added to the entity's sort position within its layer when y-sorting.
+    /// A positive bias draws the entity over others it would usually be drawn under
+    pub sort_bias: Scalar,
+}
+
+/// Tracks an entity's progress between updates so it can be drawn smoothly
+#[derive(Debug, Clone, Default)]
+struct UpdateProgress {
+    ticks_until_update: u32,
+    previous_pos: Option<Point>,
+}
+
+#[derive(Debug, Serialize, Deserialize, HasBoxMut, HasHealth, HasUuid, HasSolidity)]
 /// The basic structure of any non-static object in a game state
 pub struct Entity {
     velocity: Velocity,
@@ -44,6 +118,16 @@ pub struct Entity {
     solidity: Solidity,
     #[has_uuid]
     uuid: Uuid,
+    #[serde(default)]
+    material: PhysicsMaterial,
+    #[serde(default)]
+    update_rate: UpdateRate,
+    #[serde(default)]
+    visibility: RenderVisibility,
+    #[serde(default)]
+    render_order: RenderOrder,
+    #[serde(skip)]
+    update_progress: UpdateProgress,
 }
 
 impl Entity {
@@ -56,6 +140,11 @@ impl Entity {
             health: Health::new(10.0),
             solidity: SOLID,
             uuid: Uuid::new_v4(),
+            material: PhysicsMaterial::default(),
+            update_rate: UpdateRate::default(),
+            visibility: RenderVisibility::default(),
+            render_order: RenderOrder::default(),
+            update_progress: UpdateProgress::default(),
         })
     }
 
@@ -93,6 +182,120 @@ impl Entity {
         self.max_velocity
     }
 
+    /// Sets how the entity can be moved into and out of, i.e. [NO_SOLIDITY](crate::types::NO_SOLIDITY)
+    /// for a trigger area that other entities pass through
+    pub fn set_solidity(&mut self, solidity: Solidity) {
+        self.solidity = solidity;
+    }
+
+    /// Gets the entity's surface material
+    pub fn material(&self) -> &PhysicsMaterial {
+        &self.material
+    }
+
+    /// Sets the entity's surface material
+    pub fn set_material(&mut self, material: PhysicsMaterial) {
+        self.material = material;
+    }
+
+    /// Gets how often the entity is updated
+    pub fn update_rate(&self) -> UpdateRate {
+        self.update_rate
+    }
+
+    /// Sets how often the entity is updated. Between updates the entity is drawn
+    /// interpolated between its last two positions
+    pub fn set_update_rate(&mut self, update_rate: UpdateRate) {
+        self.update_rate = update_rate;
+        self.update_progress = UpdateProgress::default();
+    }
+
+    /// Gets whether the entity is drawn
+    pub fn visibility(&self) -> RenderVisibility {
+        self.visibility
+    }
+
+    /// Sets whether the entity is drawn
+    pub fn set_visibility(&mut self, visibility: RenderVisibility) {
+        self.visibility = visibility;
+    }
+
+    /// Gets where the entity is drawn relative to other entities
+    pub fn render_order(&self) -> RenderOrder {
+        self.render_order
+    }
+
+    /// Sets where the entity is drawn relative to other entities
+    pub fn set_render_order(&mut self, render_order: RenderOrder) {
+        self.render_order = render_order;
+    }
+
+    /// The z layer and position within that layer that the entity is sorted by when drawn
+    pub fn sort_key(&self) -> (i32, Scalar) {
+        (
+            self.render_order.z_layer,
+            self.get_render_box().bottom_y() + self.render_order.sort_bias,
+        )
+    }
+
+    /// Advances the entity by one game tick, returning true when the entity should be
+    /// updated this tick according to its [UpdateRate]
+    pub fn begin_tick(&mut self) -> bool {
+        if self.update_progress.ticks_until_update > 0 {
+            self.update_progress.ticks_until_update -= 1;
+            return false;
+        }
+
+        self.update_progress.ticks_until_update = self.update_rate.ticks_per_update - 1;
+        self.update_progress.previous_pos = Some(*self.physbox.pos());
+        true
+    }
+
+    /// Moves the entity to `pos` without drawing it sliding there from where it was
+    pub fn teleport(&mut self, pos: Point) {
+        self.physbox.set_pos(pos);
+        self.update_progress.previous_pos = None;
+    }
+
+    /// Moves the entity along with the position it's drawn moving from, i.e. when the whole
+    /// world is shifted, so that it keeps being drawn moving smoothly
+    pub fn shift(&mut self, offset: Point) {
+        let shifted = |pos: Point| Point {
+            x: pos.x + offset.x,
+            y: pos.y + offset.y,
+        };
+        self.physbox.set_pos(shifted(*self.physbox.pos()));
+        self.update_progress.previous_pos = self.update_progress.previous_pos.map(shifted);
+    }
+
+    /// Updates the entity's velocity after touching something with the `other` material
+    /// on the entity's given `edge` for `dt` seconds
+    pub fn apply_contact(
+        &mut self,
+        edge: BoxEdge,
+        other: &PhysicsMaterial,
+        rules: &MaterialCombineRules,
+        dt: f64,
+    ) {
+        let (friction, restitution) = rules.combine(&self.material, other);
+        let velocity = self.velocity.after_contact(edge, friction, restitution, dt);
+
+        self.velocity = if rules.keep_velocity_into_surfaces {
+            match edge.perpendicular_axis() {
+                Axis::X => Velocity {
+                    x: self.velocity.x,
+                    y: velocity.y,
+                },
+                Axis::Y => Velocity {
+                    x: velocity.x,
+                    y: self.velocity.y,
+                },
+            }
+        } else {
+            velocity
+        };
+    }
+
     /// Get the position of the entity after its next movement assuming no collisions
     pub fn get_projected_box(&self) -> PhysBox {
         let mut physbox = self.physbox.clone();
@@ -102,12 +305,17 @@ impl Entity {
 
     /// Returns a callback function for resolving entity-entity collisions
     pub fn game_tick<E>(&mut self, args: &GameTickArgs<E>) -> PhysicsResult<()> {
-        self.try_move(args.field, args.dt)
+        self.try_move(args.field, args.dt, args.material_rules)
     }
 
     /// Attempts to move this entity according to its velocity until it collides
     /// with any cells
-    fn try_move(&mut self, field: &Field, dt: f64) -> PhysicsResult<()> {
+    fn try_move(
+        &mut self,
+        field: &Field,
+        dt: f64,
+        material_rules: &MaterialCombineRules,
+    ) -> PhysicsResult<()> {
         if self.velocity == [0.0, 0.0].into() {
             return Ok(());
         }
@@ -125,6 +333,8 @@ impl Entity {
         let new_pos = *self.physbox.pos() + self.velocity * dt;
         let mut new_box = self.physbox.clone();
         new_box.set_pos(new_pos);
+        // The edges of the entity that were stopped and the material of what stopped them
+        let mut contacts: Vec<(BoxEdge, PhysicsMaterial)> = Vec::new();
 
         // Cell Based collisions
         if !new_box.is_fully_contained_by(&current_cell.get_box()) {
@@ -134,11 +344,19 @@ impl Entity {
 
                 for (edge, neighbors_on_edge) in from_cells_neighbors.iter_by_edge() {
                     for neighbor in neighbors_on_edge {
-                        if (!from_this_cell.get_solidity().exit_edge(edge)
-                            || !neighbor.get_solidity().enter_edge(edge.opposite()))
+                        let can_exit = from_this_cell.get_solidity().exit_edge(edge);
+                        if (!can_exit || !neighbor.get_solidity().enter_edge(edge.opposite()))
                             && self.velocity.is_reduced_by_edge(edge)
                         {
                             new_box.set_touching_edge(&from_this_cell.get_box(), edge);
+                            if !contacts.iter().any(|(e, _)| *e == edge) {
+                                let stopped_by = if can_exit {
+                                    neighbor.material()
+                                } else {
+                                    from_this_cell.material()
+                                };
+                                contacts.push((edge, *stopped_by));
+                            }
                         }
                     }
 
@@ -151,6 +369,9 @@ impl Entity {
                         && from_this_cell.get_box().is_edge_crossed_by(&new_box, edge)
                     {
                         new_box.set_touching_edge(&from_this_cell.get_box(), edge);
+                        if !contacts.iter().any(|(e, _)| *e == edge) {
+                            contacts.push((edge, *from_this_cell.material()));
+                        }
                     }
                 }
 
@@ -165,6 +386,9 @@ impl Entity {
         }
 
         self.physbox = new_box;
+        for (edge, material) in contacts {
+            self.apply_contact(edge, &material, material_rules, dt);
+        }
 
         // TODO: switch to a separate "resolve entity collisions step"
         // doing these collated will definite cause problems as the number
@@ -173,6 +397,44 @@ impl Entity {
     }
 }
 
+impl HasBox for Entity {
+    fn get_box(&self) -> &PhysBox {
+        &self.physbox
+    }
+
+    fn get_render_box(&self) -> PhysBox {
+        let previous = match self.update_progress.previous_pos {
+            Some(previous) => previous,
+            None => return self.physbox,
+        };
+
+        let ticks_per_update = self.update_rate.ticks_per_update;
+        let ticks_elapsed = ticks_per_update - self.update_progress.ticks_until_update;
+        let t = ticks_elapsed as f64 / ticks_per_update as f64;
+        let current = *self.physbox.pos();
+
+        let mut render_box = self.physbox;
+        render_box.set_pos(Point {
+            x: previous.x + (current.x - previous.x) * t,
+            y: previous.y + (current.y - previous.y) * t,
+        });
+        render_box
+    }
+}
+
+impl StateHash for Entity {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.physbox.hash_state(hasher);
+        self.velocity.hash_state(hasher);
+        hasher.write_scalar(self.max_velocity);
+        self.health.hash_state(hasher);
+        self.solidity.hash_state(hasher);
+        self.material.hash_state(hasher);
+        hasher.write_u32(self.update_rate.ticks_per_update);
+        hasher.write_u32(self.update_progress.ticks_until_update);
+    }
+}
+
 impl HasEntity for Entity {
     fn get_entity(&self) -> &Entity {
         self
@@ -202,7 +464,7 @@ impl View for EntityView {
         _texture_registry: &TextureRegistry,
         gl: &mut GlGraphics,
     ) -> RenderResult<()> {
-        if let Some((transform, rect)) = camera.box_renderables(&viewed.physbox, ctx) {
+        if let Some((transform, rect)) = camera.box_renderables(&viewed.get_render_box(), ctx) {
             graphics::rectangle(self.color, rect, transform, gl);
         }
 
@@ -214,7 +476,30 @@ impl View for EntityView {
 mod test {
     use super::*;
 
-    use crate::PhysicsError;
+    use crate::{
+        gameobject::field::Cell,
+        types::{material::MaterialCombine, physbox::HasBoxMut, NO_SOLIDITY},
+        PhysicsError,
+    };
+
+    #[test]
+    fn deserialized_update_rate_is_at_least_one() {
+        use serde::de::value::{Error, MapDeserializer};
+
+        let saved = MapDeserializer::<_, Error>::new([("ticks_per_update", 0_u32)].into_iter());
+        let rate = UpdateRate::deserialize(saved).unwrap();
+        assert_eq!(rate, UpdateRate::every(1));
+    }
+
+    #[test]
+    fn teleporting_skips_interpolation() {
+        let mut entity = Entity::new().unwrap();
+        entity.set_update_rate(UpdateRate::every(4));
+        assert!(entity.begin_tick());
+
+        entity.teleport([40.0, 0.0].into());
+        assert_eq!(entity.get_render_box().pos().x, 40.0);
+    }
 
     // Doing a lot of square roots with the vector math propogates the floating-point error
     // a bunch, this is just to make sure it's reasonably accurate
@@ -250,4 +535,81 @@ mod test {
         entity.set_velocity(velocity);
         assert_eq!(entity.velocity, velocity);
     }
+
+    #[test]
+    fn solid_cell_material_participates_in_bounce() {
+        let mut wall = Cell::new(SOLID, PhysBox::new([10.0, 0.0, 10.0, 10.0]).unwrap());
+        wall.set_material(PhysicsMaterial::new(0.0, 1.0));
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap()),
+            wall,
+        ])
+        .unwrap();
+
+        let mut entity = Entity::new().unwrap();
+        entity.get_box_mut().set_pos([5.0, 2.0].into());
+        entity.set_max_velocity(20.0).unwrap();
+        entity.set_velocity([10.0, 0.0].into());
+
+        let bounce = MaterialCombineRules {
+            keep_velocity_into_surfaces: false,
+            ..Default::default()
+        };
+        entity.try_move(&field, 1.0, &bounce).unwrap();
+
+        // Stopped at the wall, then bounced back by the wall's restitution
+        assert_eq!(entity.get_box().right_x(), 10.0);
+        assert_eq!(entity.get_velocity(), Velocity { x: -10.0, y: 0.0 });
+
+        let mut entity = Entity::new().unwrap();
+        entity.get_box_mut().set_pos([5.0, 2.0].into());
+        entity.set_max_velocity(20.0).unwrap();
+        entity.set_velocity([10.0, 0.0].into());
+
+        let no_bounce = MaterialCombineRules {
+            restitution: MaterialCombine::Min,
+            keep_velocity_into_surfaces: false,
+            ..Default::default()
+        };
+        entity.try_move(&field, 1.0, &no_bounce).unwrap();
+
+        // No bounce stops the entity against the wall
+        assert_eq!(entity.get_box().right_x(), 10.0);
+        assert_eq!(entity.get_velocity(), Velocity { x: 0.0, y: 0.0 });
+
+        let mut entity = Entity::new().unwrap();
+        entity.get_box_mut().set_pos([5.0, 2.0].into());
+        entity.set_max_velocity(20.0).unwrap();
+        entity.set_velocity([10.0, 0.0].into());
+
+        // The default rules keep the velocity into the wall
+        entity
+            .try_move(&field, 1.0, &MaterialCombineRules::default())
+            .unwrap();
+
+        assert_eq!(entity.get_box().right_x(), 10.0);
+        assert_eq!(entity.get_velocity(), Velocity { x: 10.0, y: 0.0 });
+    }
+
+    #[test]
+    fn default_entity_keeps_velocity_into_default_wall() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([10.0, 0.0, 10.0, 10.0]).unwrap()),
+        ])
+        .unwrap();
+
+        let mut entity = Entity::new().unwrap();
+        entity.get_box_mut().set_pos([5.0, 2.0].into());
+        entity.set_max_velocity(20.0).unwrap();
+        entity.set_velocity([10.0, 3.0].into());
+
+        entity
+            .try_move(&field, 1.0, &MaterialCombineRules::default())
+            .unwrap();
+
+        // Stopped at the wall without losing any velocity, like before materials existed
+        assert_eq!(entity.get_box().right_x(), 10.0);
+        assert_eq!(entity.get_velocity(), Velocity { x: 10.0, y: 3.0 });
+    }
 }
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use graphics::Context;
 use opengl_graphics::GlGraphics;
 use piston::RenderArgs;
@@ -10,10 +12,12 @@ use crate::{
     scene::GameTickArgs,
     types::{
         physbox::{HasBox, HasBoxMut, PhysBox},
+        state_hash::{StateHash, StateHasher},
         HasHealth, HasSolidity, HasUuid,
     },
     ScarabResult,
 };
+use uuid::Uuid;
 
 // TODO: Eventually meant to be a trait that can be derived for enums whose
 // variants impl HasEntity
@@ -45,6 +49,34 @@ where
             .map_err(|e| e.into())
     }
 
+    /// The texture that the registered entity is currently drawn with, if it's drawn from a single texture.
+    /// Entities that return `Some` can have their draws grouped with others using the same texture
+    /// under [RenderStrategy::Batched](crate::rendering::batch::RenderStrategy::Batched)
+    fn batch_texture(&self) -> Option<&Path> {
+        None
+    }
+
+    /// The area (in world coordinates) that the registered entity covers when it's drawn,
+    /// which can reach outside of its box, i.e. for a sprite larger than the entity.
+    /// Entities that return `None` are treated as overlapping every other draw, so they're never
+    /// reordered under [RenderStrategy::Batched](crate::rendering::batch::RenderStrategy::Batched)
+    fn draw_bounds(&self) -> Option<PhysBox> {
+        None
+    }
+
+    /// Resizes the inner entity's box to its sprite, i.e. with [SpriteAnimation::fit_box](crate::rendering::sprite::SpriteAnimation::fit_box).
+    /// The scene calls this at the start of every tick, before the entity's game tick. By default does nothing
+    fn fit_box_to_sprite(&mut self) -> ScarabResult<()> {
+        Ok(())
+    }
+
+    /// Writes the registered entity's simulation state to a scene's state hash.
+    /// By default only hashes the inner entity, so any additional game state that affects
+    /// the simulation should be added by overriding this
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.inner_entity().hash_state(hasher)
+    }
+
     /// Controls how the registered object renders the inner entity.
     /// This should usually be done by pairing the registered entity with something that impls [crate::rendering::View]
     fn render(
@@ -61,6 +93,10 @@ impl<E: RegisteredEntity> HasBox for E {
     fn get_box(&self) -> &PhysBox {
         self.inner_entity().get_box()
     }
+
+    fn get_render_box(&self) -> PhysBox {
+        self.inner_entity().get_render_box()
+    }
 }
 
 impl<E: RegisteredEntity> HasBoxMut for E {
@@ -172,6 +208,21 @@ impl<E: RegisteredEntity> EntityRegistry<E> {
     pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, E> {
         self.inner.iter_mut()
     }
+
+    /// Returns the id of the first entity, in registration order, that satisfies `pred`.
+    /// Stops checking entities as soon as one matches
+    pub fn find<F: Fn(&E) -> bool>(&self, pred: F) -> Option<Uuid> {
+        self.inner.iter().find(|e| pred(e)).map(|e| e.uuid())
+    }
+
+    /// Iterates, in registration order, across the ids of all entities that satisfy `pred`.
+    /// The predicate is only run as the iterator is advanced
+    pub fn filter<'a, F: Fn(&E) -> bool + 'a>(
+        &'a self,
+        pred: F,
+    ) -> impl Iterator<Item = Uuid> + 'a {
+        self.inner.iter().filter(move |e| pred(e)).map(|e| e.uuid())
+    }
 }
 
 impl<E> IntoIterator for EntityRegistry<E> {
@@ -200,3 +251,142 @@ impl<'a, E> IntoIterator for &'a mut EntityRegistry<E> {
         self.inner.iter_mut()
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use core::cell::Cell;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[derive(Debug)]
+    /// A bare registered entity for tests that don't care about rendering.
+    /// Can report a texture, and the rectangle `[x, y, w, h]` relative to its box that it's drawn over
+    pub(crate) struct TestEntity(pub Entity, pub Option<PathBuf>, pub Option<[f64; 4]>);
+
+    impl TestEntity {
+        /// Creates a test entity with a box at the given position and size
+        pub(crate) fn at(physbox: [f64; 4]) -> Self {
+            let mut entity = Entity::new().unwrap();
+            *entity.get_box_mut() = PhysBox::new(physbox).unwrap();
+            Self(entity, None, None)
+        }
+
+        /// Sets the texture the test entity reports it's drawn with, covering exactly its box
+        pub(crate) fn with_texture(self, texture: &str) -> Self {
+            let size = *self.0.get_box().size();
+            self.with_sprite(texture, [0.0, 0.0, size.w, size.h])
+        }
+
+        /// Sets the texture the test entity reports it's drawn with,
+        /// covering `rect` relative to the top left of its box
+        pub(crate) fn with_sprite(mut self, texture: &str, rect: [f64; 4]) -> Self {
+            self.1 = Some(texture.into());
+            self.2 = Some(rect);
+            self
+        }
+    }
+
+    impl RegisteredEntity for TestEntity {
+        type Player = Entity;
+
+        fn inner_entity(&self) -> &Entity {
+            &self.0
+        }
+
+        fn inner_entity_mut(&mut self) -> &mut Entity {
+            &mut self.0
+        }
+
+        fn maybe_player(&self) -> Option<&Self::Player> {
+            None
+        }
+
+        fn batch_texture(&self) -> Option<&Path> {
+            self.1.as_deref()
+        }
+
+        fn draw_bounds(&self) -> Option<PhysBox> {
+            let render_box = self.0.get_render_box();
+            self.2.and_then(|[x, y, w, h]| {
+                PhysBox::new([render_box.left_x() + x, render_box.top_y() + y, w, h]).ok()
+            })
+        }
+
+        fn maybe_player_mut(&mut self) -> Option<&mut Self::Player> {
+            None
+        }
+
+        fn render(
+            &mut self,
+            _args: &RenderArgs,
+            _camera: &Camera,
+            _ctx: Context,
+            _texture_registry: &TextureRegistry,
+            _gl: &mut GlGraphics,
+        ) -> RenderResult<()> {
+            Ok(())
+        }
+    }
+
+    fn create_test_registry() -> EntityRegistry<TestEntity> {
+        let mut registry = EntityRegistry::default();
+        for x in [0.0, 10.0, 20.0, 30.0, 40.0] {
+            registry
+                .register(TestEntity::at([x, 0.0, 5.0, 5.0]))
+                .unwrap();
+        }
+        registry
+    }
+
+    #[test]
+    fn find_returns_first_match_in_registration_order() {
+        let registry = create_test_registry();
+
+        let found = registry.find(|e| e.get_box().pos().x >= 15.0);
+        assert_eq!(found, Some(registry.get_one(2).unwrap().uuid()));
+
+        assert!(registry.find(|e| e.get_box().pos().x > 100.0).is_none());
+    }
+
+    #[test]
+    fn find_stops_at_first_match() {
+        let registry = create_test_registry();
+        let checked = Cell::new(0);
+
+        let found = registry.find(|e| {
+            checked.set(checked.get() + 1);
+            e.get_box().pos().x == 10.0
+        });
+
+        assert_eq!(found, Some(registry.get_one(1).unwrap().uuid()));
+        assert_eq!(checked.get(), 2);
+    }
+
+    #[test]
+    fn filter_returns_all_matches_in_registration_order() {
+        let mut registry = create_test_registry();
+        registry
+            .get_one_mut(3)
+            .unwrap()
+            .get_health_mut()
+            .raw_damage(5.0);
+        registry
+            .get_one_mut(1)
+            .unwrap()
+            .get_health_mut()
+            .raw_damage(5.0);
+
+        let damaged: Vec<Uuid> = registry
+            .filter(|e| e.get_health().fraction() < 1.0)
+            .collect();
+
+        assert_eq!(
+            damaged,
+            vec![
+                registry.get_one(1).unwrap().uuid(),
+                registry.get_one(3).unwrap().uuid()
+            ]
+        );
+    }
+}
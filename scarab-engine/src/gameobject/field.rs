@@ -1,5 +1,5 @@
-use core::slice::Iter;
-use std::fmt::Debug;
+use core::{hash::Hasher, slice::Iter};
+use std::{collections::HashMap, fmt::Debug, time::Instant};
 
 use graphics::{types::Color, Context};
 use opengl_graphics::GlGraphics;
@@ -10,8 +10,13 @@ use shapes::Point;
 
 use crate::{
     error::RenderResult,
-    rendering::{registry::TextureRegistry, Camera, View},
-    types::{physbox::PhysBox, BoxEdge, Solidity, NO_SOLIDITY, SOLID},
+    rendering::{registry::TextureRegistry, sprite::SpriteAnimation, Camera, View},
+    types::{
+        material::PhysicsMaterial,
+        physbox::PhysBox,
+        state_hash::{StateHash, StateHasher},
+        Axis, BoxEdge, Solidity, NO_SOLIDITY, SOLID,
+    },
     HasBox, HasBoxMut, HasSolidity, PhysicsError, PhysicsResult,
 };
 
@@ -175,6 +180,99 @@ impl Field {
         Field::cell_at_pos_internal(self.graph.node_weights(), pos)
     }
 
+    /// Whether any completely [solid](SOLID) cell is in the way of the line segment from `from` to `to`
+    pub fn is_segment_blocked(&self, from: Point, to: Point) -> bool {
+        self.graph
+            .node_weights()
+            .any(|cell| cell.solidity == SOLID && cell.physbox.intersects_segment(from, to))
+    }
+
+    /// The smallest box containing every cell on the field, if it has any cells
+    pub fn bounds(&self) -> Option<PhysBox> {
+        let mut cells = self.graph.node_weights();
+        let first = cells.next()?.physbox;
+        let (mut left, mut top, mut right, mut bottom) = (
+            first.left_x(),
+            first.top_y(),
+            first.right_x(),
+            first.bottom_y(),
+        );
+
+        for cell in cells {
+            left = f64::min(left, cell.physbox.left_x());
+            top = f64::min(top, cell.physbox.top_y());
+            right = f64::max(right, cell.physbox.right_x());
+            bottom = f64::max(bottom, cell.physbox.bottom_y());
+        }
+
+        PhysBox::new([left, top, right - left, bottom - top]).ok()
+    }
+
+    /// Grows the field outward from the given edge by adding `count` strips of cells.
+    /// Each strip is a copy of `fill_cell` stretched to span the field's [bounds](Field::bounds)
+    /// along that edge, and is as thick as `fill_cell` is perpendicular to the edge.
+    ///
+    /// Existing cells keep their positions, solidity and material, so anything positioned on the
+    /// field (entities, the camera) stays valid. Expanding from the top or left adds cells at
+    /// smaller (possibly negative) coordinates rather than moving the field's origin.
+    ///
+    /// The strips start outside the bounds, so they never overlap existing cells. On a field
+    /// that isn't rectangular, any notch along the expanded edge is left as a gap between
+    /// the existing cells and the new strips
+    pub fn expand(&mut self, edge: BoxEdge, count: usize, fill_cell: Cell) -> PhysicsResult<()> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        let bounds = self.bounds().unwrap_or(fill_cell.physbox);
+        let thickness = match edge.perpendicular_axis() {
+            Axis::X => fill_cell.physbox.size().w,
+            Axis::Y => fill_cell.physbox.size().h,
+        };
+        let total = thickness * count as f64;
+
+        for k in 0..count {
+            let offset = thickness * k as f64;
+            let strip = match edge {
+                BoxEdge::Top => [
+                    bounds.left_x(),
+                    bounds.top_y() - total + offset,
+                    bounds.size().w,
+                    thickness,
+                ],
+                BoxEdge::Left => [
+                    bounds.left_x() - total + offset,
+                    bounds.top_y(),
+                    thickness,
+                    bounds.size().h,
+                ],
+                BoxEdge::Bottom => [
+                    bounds.left_x(),
+                    bounds.bottom_y() + offset,
+                    bounds.size().w,
+                    thickness,
+                ],
+                BoxEdge::Right => [
+                    bounds.right_x() + offset,
+                    bounds.top_y(),
+                    thickness,
+                    bounds.size().h,
+                ],
+            };
+
+            let mut cell = fill_cell.clone();
+            cell.physbox = PhysBox::new(strip)?;
+            let i = self.graph.add_node(cell);
+            self.graph.node_weight_mut(i).map(|c| c.i = i);
+        }
+
+        // The existing edges may now be missing neighbors, so rebuild all of them
+        self.graph.clear_edges();
+        Field::build_cells(&mut self.graph)?;
+
+        Ok(())
+    }
+
     /// Given a cell on the field and a physbox, returns the neighbors of
     /// the cell that the physbox overlaps.
     pub fn neighbors_of_cell_overlapping_box(
@@ -195,6 +293,16 @@ impl Field {
     }
 }
 
+impl StateHash for Field {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        // Cells are stored in the order they were added, so this is deterministic
+        hasher.write_usize(self.graph.node_count());
+        for cell in self.graph.node_weights() {
+            cell.hash_state(hasher);
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Renders a Field, coloring all cells with a color determined by their solidity
 /// i.e. all cells of a unique solidity are a single color
@@ -237,6 +345,114 @@ impl View for FieldColorView {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An animation shared by every cell of a tile type
+pub struct TileAnimation {
+    animation: SpriteAnimation,
+    stagger: usize,
+}
+
+impl TileAnimation {
+    /// Creates a tile animation where every cell shows the same frame
+    pub fn new(animation: SpriteAnimation) -> Self {
+        Self {
+            animation,
+            stagger: 0,
+        }
+    }
+
+    /// Staggers the animation so that each cell is `frames` frames ahead of the cell before it,
+    /// so that large areas of the same tile don't all move in lockstep
+    pub fn with_stagger(mut self, frames: usize) -> Self {
+        self.stagger = frames;
+        self
+    }
+
+    /// The frame of the animation to draw on the given cell
+    pub fn frame_for(&self, cell: &Cell) -> usize {
+        let frame_count = self.animation.frame_count();
+        if frame_count == 0 {
+            return 0;
+        }
+
+        (self.animation.current_frame() + cell.i.index() * self.stagger) % frame_count
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Renders a Field by tiling each cell that has a [tile type](Cell::tile) with that type's animation.
+/// Cells without a tile, or with a tile that has no animation, are drawn by the fallback view
+///
+/// Each tile type's animation is advanced once per frame no matter how many cells use it,
+/// so every cell of a type stays in sync
+pub struct FieldTileView {
+    /// The animation for each tile type
+    pub tiles: HashMap<String, TileAnimation>,
+    /// The view for cells without an animated tile
+    pub fallback: FieldColorView,
+}
+
+impl FieldTileView {
+    /// Creates a tile view with no tile animations
+    pub fn new(fallback: FieldColorView) -> Self {
+        Self {
+            tiles: HashMap::new(),
+            fallback,
+        }
+    }
+
+    /// Sets the animation drawn on cells of the given tile type
+    pub fn set_tile_animation(&mut self, tile: String, animation: TileAnimation) {
+        self.tiles.insert(tile, animation);
+    }
+
+    /// The animation drawn on the given cell, if it has one
+    pub fn animation_for(&self, cell: &Cell) -> Option<&TileAnimation> {
+        cell.tile().and_then(|tile| self.tiles.get(tile))
+    }
+
+    /// Moves all of the tile animations forward to the frame that should be showing at `now`
+    fn advance_to(&mut self, now: Instant) {
+        for tile in self.tiles.values_mut() {
+            tile.animation.advance_to(now);
+        }
+    }
+}
+
+impl View for FieldTileView {
+    type Viewed = Field;
+
+    fn render(
+        &mut self,
+        viewed: &Self::Viewed,
+        args: &RenderArgs,
+        camera: &Camera,
+        ctx: Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut GlGraphics,
+    ) -> RenderResult<()> {
+        self.advance_to(camera.time_source().now());
+
+        for cell in viewed.graph.node_weights() {
+            match self.animation_for(cell) {
+                Some(tile) => tile.animation.render_frame_tiled(
+                    cell,
+                    tile.frame_for(cell),
+                    camera,
+                    ctx,
+                    texture_registry,
+                    gl,
+                ),
+                None => {
+                    let cell_view = self.fallback.view_for_cell(cell);
+                    cell_view.render(cell, args, camera, ctx, texture_registry, gl)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, HasBox, HasBoxMut, HasSolidity)]
 /// Represents a static area on a Field that determines the passability for other standard entities
 pub struct Cell {
@@ -248,6 +464,12 @@ pub struct Cell {
     #[has_box]
     /// The upper left corner and width/height of the cell
     physbox: PhysBox,
+    #[serde(default)]
+    /// The surface material used when entities collide with this cell
+    material: PhysicsMaterial,
+    #[serde(default)]
+    /// The type of tile drawn on this cell by a [FieldTileView], if any
+    tile: Option<String>,
 }
 
 impl Cell {
@@ -257,8 +479,38 @@ impl Cell {
             i: NodeIndex::new(0),
             solidity,
             physbox,
+            material: PhysicsMaterial::default(),
+            tile: None,
         }
     }
+
+    /// Gets the cell's surface material
+    pub fn material(&self) -> &PhysicsMaterial {
+        &self.material
+    }
+
+    /// Sets the cell's surface material
+    pub fn set_material(&mut self, material: PhysicsMaterial) {
+        self.material = material;
+    }
+
+    /// Gets the type of tile drawn on this cell
+    pub fn tile(&self) -> Option<&str> {
+        self.tile.as_deref()
+    }
+
+    /// Sets the type of tile drawn on this cell, or `None` to draw it without a tile
+    pub fn set_tile(&mut self, tile: Option<String>) {
+        self.tile = tile;
+    }
+}
+
+impl StateHash for Cell {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.physbox.hash_state(hasher);
+        self.solidity.hash_state(hasher);
+        self.material.hash_state(hasher);
+    }
 }
 
 /// Represents the neighbors of a cell organized by what edge the neighbor is on
@@ -372,7 +624,15 @@ impl View for CellColorView {
 
 #[cfg(test)]
 mod test {
-    use crate::types::physbox::HasBox;
+    use std::time::Duration;
+
+    use crate::{
+        rendering::{
+            sprite::test::create_test_animation,
+            time::{MockClock, TimeSource},
+        },
+        types::physbox::HasBox,
+    };
 
     use super::*;
 
@@ -545,4 +805,156 @@ mod test {
             &vec![field.cell_at_pos(*boxes[3].pos()).unwrap()]
         );
     }
+
+    #[test]
+    fn expand_preserves_existing_cells() {
+        let (boxes, mut field) = create_test_field();
+        let original: Vec<Cell> = field.graph.node_weights().cloned().collect();
+
+        let fill = Cell::new(SOLID, PhysBox::new([0.0, 0.0, 1.0, 2.0]).unwrap());
+        field.expand(BoxEdge::Right, 2, fill).unwrap();
+
+        for cell in &original {
+            assert_eq!(field.cell_at_pos(*cell.physbox.pos()).unwrap(), cell);
+        }
+
+        // The new cells span the full height to the right of the old field
+        let new_cell = field.cell_at_pos([51.0, -1.0].into()).unwrap();
+        assert_eq!(
+            new_cell.get_box(),
+            &PhysBox::new([51.0, -1.0, 1.0, 62.0]).unwrap()
+        );
+        assert_eq!(new_cell.get_solidity(), &SOLID);
+        assert!(field.cell_at_pos([52.0, 30.0].into()).is_some());
+        assert!(field.cell_at_pos([53.0, 30.0].into()).is_none());
+        assert_eq!(field.bounds(), PhysBox::new([-1.0, -1.0, 54.0, 62.0]).ok());
+
+        assert_eq!(
+            field.cell_at_pos(*boxes[3].pos()).unwrap().get_box(),
+            &boxes[3]
+        );
+    }
+
+    #[test]
+    fn expand_negative_direction_keeps_existing_cells_in_place() {
+        let (boxes, mut field) = create_test_field();
+        let original: Vec<Cell> = field.graph.node_weights().cloned().collect();
+
+        let fill = Cell::new(SOLID, PhysBox::new([0.0, 0.0, 1.0, 2.0]).unwrap());
+        field.expand(BoxEdge::Top, 3, fill).unwrap();
+
+        for cell in &original {
+            assert_eq!(field.cell_at_pos(*cell.physbox.pos()).unwrap(), cell);
+        }
+
+        // The new cells are added above where the field used to start
+        for y in [-7.0, -5.0, -3.0] {
+            let new_cell = field.cell_at_pos([-1.0, y].into()).unwrap();
+            assert_eq!(
+                new_cell.get_box(),
+                &PhysBox::new([-1.0, y, 52.0, 2.0]).unwrap()
+            );
+        }
+        assert_eq!(field.bounds(), PhysBox::new([-1.0, -7.0, 52.0, 68.0]).ok());
+
+        // The cell graph was rebuilt to connect the new and old cells
+        let bottom_strip = field.cell_at_pos([-1.0, -3.0].into()).unwrap();
+        let testbox = PhysBox::new([10.0, -2.0, 2.0, 2.0]).unwrap();
+        let neighbors = field
+            .neighbors_of_cell_overlapping_box(bottom_strip, &testbox)
+            .unwrap();
+        assert_eq!(
+            neighbors.get_neighbors(BoxEdge::Bottom),
+            &vec![field.cell_at_pos(*boxes[8].pos()).unwrap()]
+        );
+    }
+
+    #[test]
+    fn expand_leaves_notches_in_non_rectangular_fields_as_gaps() {
+        // An L shape with a notch in its top right
+        let mut field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 20.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([10.0, 10.0, 10.0, 10.0]).unwrap()),
+        ])
+        .unwrap();
+        let original: Vec<Cell> = field.graph.node_weights().cloned().collect();
+
+        let fill = Cell::new(SOLID, PhysBox::new([0.0, 0.0, 1.0, 1.0]).unwrap());
+        field.expand(BoxEdge::Top, 1, fill).unwrap();
+
+        let strip = field.cell_at_pos([0.0, -1.0].into()).unwrap();
+        assert_eq!(
+            strip.get_box(),
+            &PhysBox::new([0.0, -1.0, 20.0, 1.0]).unwrap()
+        );
+        for cell in &original {
+            assert!(!strip.get_box().has_overlap(cell.get_box()));
+        }
+        // The notch isn't filled in
+        assert!(field.cell_at_pos([15.0, 5.0].into()).is_none());
+    }
+
+    fn create_tile_field() -> Field {
+        let mut water = Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap());
+        water.set_tile(Some("water".to_string()));
+        let mut more_water = Cell::new(NO_SOLIDITY, PhysBox::new([10.0, 0.0, 10.0, 10.0]).unwrap());
+        more_water.set_tile(Some("water".to_string()));
+        let ground = Cell::new(SOLID, PhysBox::new([0.0, 10.0, 20.0, 10.0]).unwrap());
+        let mut unanimated = Cell::new(SOLID, PhysBox::new([20.0, 0.0, 10.0, 20.0]).unwrap());
+        unanimated.set_tile(Some("lava".to_string()));
+
+        Field::new(vec![water, more_water, ground, unanimated]).unwrap()
+    }
+
+    fn create_tile_view(water: TileAnimation) -> FieldTileView {
+        let color_view = CellColorView { color: [1.0; 4] };
+        let mut view = FieldTileView::new(FieldColorView {
+            solid_view: color_view.clone(),
+            air_view: color_view.clone(),
+            default_view: color_view,
+        });
+        view.set_tile_animation("water".to_string(), water);
+        view
+    }
+
+    #[test]
+    fn animated_tiles_advance_together() {
+        let field = create_tile_field();
+        let cells: Vec<&Cell> = field.graph.node_weights().collect();
+        let clock = MockClock::new();
+        let mut view = create_tile_view(TileAnimation::new(create_test_animation(100.0, 4)));
+        view.advance_to(clock.now());
+
+        clock.advance(Duration::from_millis(250));
+        view.advance_to(clock.now());
+        let water = view.animation_for(cells[0]).unwrap();
+        assert_eq!(water.frame_for(cells[0]), 2);
+        assert_eq!(water.frame_for(cells[1]), 2);
+
+        clock.advance(Duration::from_millis(110));
+        view.advance_to(clock.now());
+        let water = view.animation_for(cells[1]).unwrap();
+        assert_eq!(water.frame_for(cells[0]), 3);
+        assert_eq!(water.frame_for(cells[1]), 3);
+
+        // Cells without an animated tile fall back to the static view
+        assert!(view.animation_for(cells[2]).is_none());
+        assert!(view.animation_for(cells[3]).is_none());
+    }
+
+    #[test]
+    fn staggered_tiles_are_phase_offset() {
+        let field = create_tile_field();
+        let cells: Vec<&Cell> = field.graph.node_weights().collect();
+        let clock = MockClock::new();
+        let mut view =
+            create_tile_view(TileAnimation::new(create_test_animation(100.0, 4)).with_stagger(1));
+        view.advance_to(clock.now());
+
+        clock.advance(Duration::from_millis(250));
+        view.advance_to(clock.now());
+        let water = view.animation_for(cells[0]).unwrap();
+        assert_eq!(water.frame_for(cells[0]), 2);
+        assert_eq!(water.frame_for(cells[1]), 3);
+    }
 }
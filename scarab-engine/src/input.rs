@@ -81,6 +81,28 @@ pub enum UnitAxis2dBinding {
     // TODO: add support for joysticks etc.
 }
 
+impl UnitAxis2dBinding {
+    /// Sets how opposite directions held at the same time are resolved
+    pub fn with_socd_mode(mut self, socd_mode: SocdMode) -> Self {
+        self.set_socd_mode(socd_mode);
+        self
+    }
+
+    /// Sets how opposite directions held at the same time are resolved
+    pub fn set_socd_mode(&mut self, socd_mode: SocdMode) {
+        match self {
+            UnitAxis2dBinding::LogicalDpad(dpad) => dpad.set_socd_mode(socd_mode),
+        }
+    }
+
+    /// How opposite directions held at the same time are resolved
+    pub fn socd_mode(&self) -> SocdMode {
+        match self {
+            UnitAxis2dBinding::LogicalDpad(dpad) => dpad.socd_mode(),
+        }
+    }
+}
+
 impl InputBinding for UnitAxis2dBinding {
     type ActionArg = [f64; 2];
 
@@ -106,6 +128,24 @@ pub enum LogicalDpad {
     PhysicalDpad(ControllerHat),
 }
 
+impl LogicalDpad {
+    /// Sets how opposite directions held at the same time are resolved.
+    /// A physical D-pad can't report opposite directions at once, so this only affects virtual D-pads
+    pub fn set_socd_mode(&mut self, socd_mode: SocdMode) {
+        if let LogicalDpad::VirtualDpad(dpad) = self {
+            dpad.set_socd_mode(socd_mode);
+        }
+    }
+
+    /// How opposite directions held at the same time are resolved. Always [SocdMode::Neutral] for physical D-pads
+    pub fn socd_mode(&self) -> SocdMode {
+        match self {
+            LogicalDpad::VirtualDpad(dpad) => dpad.socd_mode(),
+            LogicalDpad::PhysicalDpad(_) => SocdMode::Neutral,
+        }
+    }
+}
+
 impl InputBinding for LogicalDpad {
     type ActionArg = [f64; 2];
 
@@ -159,6 +199,19 @@ fn hat_state_to_action_arg(value: HatState) -> [f64; 2] {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// How a [UnitAxis2dBinding] resolves opposite directions on the same axis (i.e. left and right) being held at once.
+/// Also known as SOCD (simultaneous opposing cardinal directions) resolution
+pub enum SocdMode {
+    /// Opposite directions cancel each other out
+    #[default]
+    Neutral,
+    /// The most recently pressed direction wins
+    LastInputPriority,
+    /// The positive direction wins
+    PositivePriority,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// A logical representation of a 2-axis D-pad
 pub struct VirtualDpad {
@@ -166,6 +219,11 @@ pub struct VirtualDpad {
     neg_x: (SingleButton, f64),
     pos_y: (SingleButton, f64),
     neg_y: (SingleButton, f64),
+    #[serde(default)]
+    socd_mode: SocdMode,
+    /// The most recently pressed direction on each axis. Runtime state, so it isn't saved with the bindings
+    #[serde(skip)]
+    last_pressed: (Option<Axis2dDirection>, Option<Axis2dDirection>),
 }
 
 impl VirtualDpad {
@@ -181,13 +239,81 @@ impl VirtualDpad {
             pos_y: (pos_y, 0.0),
             neg_x: (neg_x, 0.0),
             neg_y: (neg_y, 0.0),
+            socd_mode: SocdMode::default(),
+            last_pressed: (None, None),
+        }
+    }
+
+    /// Sets how opposite directions held at the same time are resolved
+    pub fn with_socd_mode(mut self, socd_mode: SocdMode) -> Self {
+        self.socd_mode = socd_mode;
+        self
+    }
+
+    /// Sets how opposite directions held at the same time are resolved
+    pub fn set_socd_mode(&mut self, socd_mode: SocdMode) {
+        self.socd_mode = socd_mode;
+    }
+
+    /// How opposite directions held at the same time are resolved
+    pub fn socd_mode(&self) -> SocdMode {
+        self.socd_mode
+    }
+
+    /// The current value of each axis after resolving opposite directions according to the [SocdMode]
+    pub fn axis_values(&self) -> [f64; 2] {
+        [
+            self.resolve_axis(
+                self.pos_x.1,
+                self.neg_x.1,
+                self.last_pressed.0,
+                Axis2dDirection::PosX,
+            ),
+            self.resolve_axis(
+                self.pos_y.1,
+                self.neg_y.1,
+                self.last_pressed.1,
+                Axis2dDirection::PosY,
+            ),
+        ]
+    }
+
+    fn resolve_axis(
+        &self,
+        pos: f64,
+        neg: f64,
+        last_pressed: Option<Axis2dDirection>,
+        positive: Axis2dDirection,
+    ) -> f64 {
+        if pos == 0.0 || neg == 0.0 {
+            return pos - neg;
+        }
+
+        match self.socd_mode {
+            SocdMode::Neutral => pos - neg,
+            SocdMode::LastInputPriority => match last_pressed {
+                Some(dir) if dir == positive => pos,
+                Some(_) => -neg,
+                None => pos - neg,
+            },
+            SocdMode::PositivePriority => pos,
         }
     }
 
     /// Sets the value for the corresponding direction to 1 or 0 depending on the button state
     fn set_axis_button(&mut self, button: ButtonState, dir: Axis2dDirection) {
         let val = match button {
-            ButtonState::Press => 1.0,
+            ButtonState::Press => {
+                match dir {
+                    Axis2dDirection::PosX | Axis2dDirection::NegX => {
+                        self.last_pressed.0 = Some(dir)
+                    }
+                    Axis2dDirection::PosY | Axis2dDirection::NegY => {
+                        self.last_pressed.1 = Some(dir)
+                    }
+                }
+                1.0
+            }
             ButtonState::Release => 0.0,
         };
         self.set_axis(val, dir)
@@ -234,8 +360,7 @@ impl InputBinding for VirtualDpad {
 
 impl From<VirtualDpad> for [f64; 2] {
     fn from(val: VirtualDpad) -> Self {
-        let mut x = val.pos_x.1 - val.neg_x.1;
-        let mut y = val.pos_y.1 - val.neg_y.1;
+        let [mut x, mut y] = val.axis_values();
         if x != 0.0 && y != 0.0 {
             x *= *ROOT_2;
             y *= *ROOT_2;
@@ -246,17 +371,17 @@ impl From<VirtualDpad> for [f64; 2] {
 
 impl From<&VirtualDpad> for [f64; 2] {
     fn from(val: &VirtualDpad) -> Self {
-        [val.pos_x.1 - val.neg_x.1, val.pos_y.1 - val.neg_y.1]
+        val.axis_values()
     }
 }
 
 impl From<&mut VirtualDpad> for [f64; 2] {
     fn from(val: &mut VirtualDpad) -> Self {
-        [val.pos_x.1 - val.neg_x.1, val.pos_y.1 - val.neg_y.1]
+        val.axis_values()
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 /// A cardinal direction on a 2-d coorinate plane
 pub enum Axis2dDirection {
     /// Positive-X direction (left)
@@ -296,3 +421,148 @@ impl InputBinding for ButtonBinding {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn button_input(key: Key, state: ButtonState) -> Input {
+        Input::Button(ButtonArgs {
+            state,
+            button: Button::Keyboard(key),
+            scancode: None,
+        })
+    }
+
+    fn create_test_dpad(socd_mode: SocdMode) -> VirtualDpad {
+        VirtualDpad::new(
+            SingleButton::Keyboard(Key::D),
+            SingleButton::Keyboard(Key::S),
+            SingleButton::Keyboard(Key::A),
+            SingleButton::Keyboard(Key::W),
+        )
+        .with_socd_mode(socd_mode)
+    }
+
+    #[test]
+    fn last_input_priority_uses_latest_direction() {
+        let mut dpad = create_test_dpad(SocdMode::LastInputPriority);
+
+        let right = dpad.maybe_to_action(&button_input(Key::D, ButtonState::Press));
+        assert_eq!(right, Some([1.0, 0.0]));
+
+        let left = dpad.maybe_to_action(&button_input(Key::A, ButtonState::Press));
+        assert_eq!(left, Some([-1.0, 0.0]));
+
+        // Still holding right, so releasing left goes back to it
+        let right = dpad.maybe_to_action(&button_input(Key::A, ButtonState::Release));
+        assert_eq!(right, Some([1.0, 0.0]));
+
+        let left = dpad.maybe_to_action(&button_input(Key::A, ButtonState::Press));
+        assert_eq!(left, Some([-1.0, 0.0]));
+        let left = dpad.maybe_to_action(&button_input(Key::D, ButtonState::Release));
+        assert_eq!(left, Some([-1.0, 0.0]));
+    }
+
+    #[test]
+    fn neutral_cancels_opposite_directions() {
+        let mut dpad = create_test_dpad(SocdMode::Neutral);
+
+        dpad.maybe_to_action(&button_input(Key::D, ButtonState::Press));
+        let neutral = dpad.maybe_to_action(&button_input(Key::A, ButtonState::Press));
+        assert_eq!(neutral, Some([0.0, 0.0]));
+
+        let right = dpad.maybe_to_action(&button_input(Key::A, ButtonState::Release));
+        assert_eq!(right, Some([1.0, 0.0]));
+    }
+
+    #[test]
+    fn positive_priority_prefers_positive_direction() {
+        let mut dpad = create_test_dpad(SocdMode::PositivePriority);
+
+        dpad.maybe_to_action(&button_input(Key::W, ButtonState::Press));
+        let down = dpad.maybe_to_action(&button_input(Key::S, ButtonState::Press));
+        assert_eq!(down, Some([0.0, 1.0]));
+
+        let up = dpad.maybe_to_action(&button_input(Key::S, ButtonState::Release));
+        assert_eq!(up, Some([0.0, -1.0]));
+    }
+
+    #[test]
+    fn socd_mode_is_per_binding() {
+        let mut last_input = create_test_dpad(SocdMode::LastInputPriority);
+        let mut neutral = create_test_dpad(SocdMode::Neutral);
+
+        for input in [
+            button_input(Key::D, ButtonState::Press),
+            button_input(Key::A, ButtonState::Press),
+        ] {
+            last_input.maybe_to_action(&input);
+            neutral.maybe_to_action(&input);
+        }
+
+        assert_eq!(last_input.axis_values(), [-1.0, 0.0]);
+        assert_eq!(neutral.axis_values(), [0.0, 0.0]);
+    }
+
+    fn create_test_axis_binding(socd_mode: SocdMode) -> UnitAxis2dBinding {
+        UnitAxis2dBinding::from(LogicalDpad::from(create_test_dpad(SocdMode::default())))
+            .with_socd_mode(socd_mode)
+    }
+
+    /// Presses right then left, then releases left, returning each resulting action
+    fn press_right_left_release_left(binding: &mut UnitAxis2dBinding) -> Vec<Option<[f64; 2]>> {
+        [
+            button_input(Key::D, ButtonState::Press),
+            button_input(Key::A, ButtonState::Press),
+            button_input(Key::A, ButtonState::Release),
+        ]
+        .iter()
+        .map(|input| binding.maybe_to_action(input))
+        .collect()
+    }
+
+    #[test]
+    fn axis_binding_last_input_priority() {
+        let mut binding = create_test_axis_binding(SocdMode::LastInputPriority);
+        assert_eq!(binding.socd_mode(), SocdMode::LastInputPriority);
+
+        assert_eq!(
+            press_right_left_release_left(&mut binding),
+            vec![Some([1.0, 0.0]), Some([-1.0, 0.0]), Some([1.0, 0.0])]
+        );
+    }
+
+    #[test]
+    fn axis_binding_neutral() {
+        let mut binding = create_test_axis_binding(SocdMode::Neutral);
+
+        assert_eq!(
+            press_right_left_release_left(&mut binding),
+            vec![Some([1.0, 0.0]), Some([0.0, 0.0]), Some([1.0, 0.0])]
+        );
+    }
+
+    #[test]
+    fn axis_binding_positive_priority() {
+        let mut binding = create_test_axis_binding(SocdMode::PositivePriority);
+
+        assert_eq!(
+            press_right_left_release_left(&mut binding),
+            vec![Some([1.0, 0.0]), Some([1.0, 0.0]), Some([1.0, 0.0])]
+        );
+    }
+
+    #[test]
+    fn physical_dpad_axis_binding_ignores_socd_mode() {
+        let hat = ControllerHat {
+            id: 0,
+            state: HatState::Centered,
+            which: 0,
+        };
+        let binding = UnitAxis2dBinding::from(LogicalDpad::from(hat))
+            .with_socd_mode(SocdMode::LastInputPriority);
+
+        assert_eq!(binding.socd_mode(), SocdMode::Neutral);
+    }
+}
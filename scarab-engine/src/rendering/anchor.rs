@@ -0,0 +1,140 @@
+use graphics::types::Scalar;
+use serde::{Deserialize, Serialize};
+
+use crate::{types::physbox::PhysBox, PhysicsResult};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+/// Margins (in points) along the edges of the window that screen-space UI is kept out of,
+/// i.e. for TV overscan or a device's notch
+pub struct SafeArea {
+    /// The margin along the top of the window
+    pub top: Scalar,
+    /// The margin along the left of the window
+    pub left: Scalar,
+    /// The margin along the bottom of the window
+    pub bottom: Scalar,
+    /// The margin along the right of the window
+    pub right: Scalar,
+}
+
+impl SafeArea {
+    /// Creates a safe area with the same margin along every edge
+    pub fn uniform(inset: Scalar) -> Self {
+        Self {
+            top: inset,
+            left: inset,
+            bottom: inset,
+            right: inset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The point of an area that something is attached to, i.e. where screen-space UI is placed within
+/// the safe area, or the point of a game object's box that stays in place when it's fit to its sprite
+pub enum Anchor {
+    /// The top left corner
+    TopLeft,
+    /// The middle of the top edge
+    Top,
+    /// The top right corner
+    TopRight,
+    /// The middle of the left edge
+    Left,
+    /// The center
+    Center,
+    /// The middle of the right edge
+    Right,
+    /// The bottom left corner, i.e. for a character standing on the ground
+    BottomLeft,
+    /// The middle of the bottom edge
+    Bottom,
+    /// The bottom right corner
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far along the area the anchor is horizontally and vertically, from 0 (left/top) to 1 (right/bottom)
+    fn alignment(&self) -> (Scalar, Scalar) {
+        match self {
+            Self::TopLeft => (0.0, 0.0),
+            Self::Top => (0.5, 0.0),
+            Self::TopRight => (1.0, 0.0),
+            Self::Left => (0.0, 0.5),
+            Self::Center => (0.5, 0.5),
+            Self::Right => (1.0, 0.5),
+            Self::BottomLeft => (0.0, 1.0),
+            Self::Bottom => (0.5, 1.0),
+            Self::BottomRight => (1.0, 1.0),
+        }
+    }
+
+    /// Places a rectangle of the given `size` at this anchor of `area`.
+    /// `offset` moves the rectangle away from the edges it's anchored to, towards the area's center.
+    /// Along an axis the rectangle is centered on, `offset` moves it right/down instead
+    pub fn place(&self, area: [Scalar; 4], size: [Scalar; 2], offset: [Scalar; 2]) -> [Scalar; 4] {
+        let (align_x, align_y) = self.alignment();
+        [
+            Self::place_along(area[0], area[2], size[0], offset[0], align_x),
+            Self::place_along(area[1], area[3], size[1], offset[1], align_y),
+            size[0],
+            size[1],
+        ]
+    }
+
+    fn place_along(
+        start: Scalar,
+        length: Scalar,
+        size: Scalar,
+        offset: Scalar,
+        alignment: Scalar,
+    ) -> Scalar {
+        let inward = if alignment == 1.0 { -offset } else { offset };
+        start + (length - size) * alignment + inward
+    }
+
+    /// The box with the given `[w, h]` size whose anchor is at the same place as `physbox`'s
+    pub fn resize(&self, physbox: &PhysBox, [w, h]: [Scalar; 2]) -> PhysicsResult<PhysBox> {
+        let (align_x, align_y) = self.alignment();
+        let anchor_x = physbox.left_x() + physbox.size().w * align_x;
+        let anchor_y = physbox.top_y() + physbox.size().h * align_y;
+        PhysBox::new([anchor_x - w * align_x, anchor_y - h * align_y, w, h])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn offsets_move_towards_the_center() {
+        let area = [0.0, 0.0, 100.0, 50.0];
+
+        assert_eq!(
+            Anchor::TopLeft.place(area, [10.0, 10.0], [2.0, 3.0]),
+            [2.0, 3.0, 10.0, 10.0]
+        );
+        assert_eq!(
+            Anchor::BottomRight.place(area, [10.0, 10.0], [2.0, 3.0]),
+            [88.0, 37.0, 10.0, 10.0]
+        );
+        assert_eq!(
+            Anchor::Center.place(area, [10.0, 10.0], [2.0, 3.0]),
+            [47.0, 23.0, 10.0, 10.0]
+        );
+    }
+
+    #[test]
+    fn resizing_keeps_the_anchor_in_place() {
+        let physbox = PhysBox::new([10.0, 10.0, 8.0, 8.0]).unwrap();
+
+        assert_eq!(
+            Anchor::Bottom.resize(&physbox, [4.0, 2.0]).unwrap(),
+            PhysBox::new([12.0, 16.0, 4.0, 2.0]).unwrap()
+        );
+        assert_eq!(
+            Anchor::TopLeft.resize(&physbox, [4.0, 2.0]).unwrap(),
+            PhysBox::new([10.0, 10.0, 4.0, 2.0]).unwrap()
+        );
+    }
+}
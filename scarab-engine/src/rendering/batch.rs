@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::physbox::PhysBox;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Controls the order that a scene's entities are drawn in
+pub enum RenderStrategy {
+    /// Draws entities one at a time in their sorted order
+    Immediate,
+    /// Draws entities in their sorted order, but groups the draws within each z layer by texture
+    /// to reduce texture binds. Draws are only moved past others that their
+    /// [draw bounds](crate::gameobject::entity::registry::RegisteredEntity::draw_bounds) don't overlap,
+    /// so the result looks the same as [RenderStrategy::Immediate]
+    #[default]
+    Batched,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// The information about a draw needed to batch it with others
+pub struct DrawItem<'a> {
+    /// The z layer the draw is on. Draws are never moved between layers
+    pub layer: i32,
+    /// The texture the draw uses, or `None` if it can't be batched
+    pub texture: Option<&'a Path>,
+    /// The area the draw covers, or `None` if it isn't known,
+    /// in which case the draw is treated as overlapping every other draw
+    pub bounds: Option<PhysBox>,
+}
+
+/// Reorders `items`, given in their immediate-mode draw order, so that draws with the same
+/// texture in the same layer are next to each other. Returns the indices of `items` in the new order.
+///
+/// A draw joins the latest earlier batch with its texture only if it doesn't overlap any draw in
+/// the batches after that one, so overlapping draws always stay in their original order
+pub fn batch_draws(items: &[DrawItem]) -> Vec<usize> {
+    let mut order = Vec::with_capacity(items.len());
+    let mut batches: Vec<(Option<&Path>, Vec<usize>)> = Vec::new();
+    let mut current_layer = None;
+
+    for (i, item) in items.iter().enumerate() {
+        if current_layer != Some(item.layer) {
+            order.extend(batches.drain(..).flat_map(|(_texture, batch)| batch));
+            current_layer = Some(item.layer);
+        }
+
+        let joinable = item.texture.and_then(|texture| {
+            let last_with_texture = batches
+                .iter()
+                .rposition(|(batch_texture, _batch)| *batch_texture == Some(texture))?;
+            let is_blocked = batches[last_with_texture + 1..]
+                .iter()
+                .flat_map(|(_texture, batch)| batch)
+                .any(|&j| {
+                    items[j]
+                        .bounds
+                        .zip(item.bounds)
+                        .map_or(true, |(a, b)| a.has_overlap(&b))
+                });
+
+            (!is_blocked).then_some(last_with_texture)
+        });
+
+        match joinable {
+            Some(batch) => batches[batch].1.push(i),
+            None => batches.push((item.texture, vec![i])),
+        }
+    }
+    order.extend(batches.into_iter().flat_map(|(_texture, batch)| batch));
+
+    order
+}
+
+/// The number of times the texture changes when drawing `items` in the given `order`.
+/// Draws without a texture don't change which texture is bound
+pub fn count_texture_binds(items: &[DrawItem], order: &[usize]) -> usize {
+    let mut bound = None;
+    let mut binds = 0;
+    for &i in order {
+        if let Some(texture) = items[i].texture {
+            if bound != Some(texture) {
+                bound = Some(texture);
+                binds += 1;
+            }
+        }
+    }
+
+    binds
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn item(layer: i32, texture: &str, x: f64) -> DrawItem {
+        DrawItem {
+            layer,
+            texture: Some(Path::new(texture)),
+            bounds: PhysBox::new([x, 0.0, 4.0, 4.0]).ok(),
+        }
+    }
+
+    #[test]
+    fn groups_textures_within_layers_but_not_across_them() {
+        let items = [
+            item(0, "grass.png", 0.0),
+            item(0, "rock.png", 10.0),
+            item(0, "grass.png", 20.0),
+            item(0, "rock.png", 30.0),
+            item(1, "grass.png", 40.0),
+            item(1, "tree.png", 50.0),
+            item(1, "grass.png", 60.0),
+        ];
+        let immediate: Vec<usize> = (0..items.len()).collect();
+
+        let batched = batch_draws(&items);
+        assert_eq!(batched, vec![0, 2, 1, 3, 4, 6, 5]);
+        // Layer 1's grass is never pulled into layer 0's grass batch
+        assert_eq!(count_texture_binds(&items, &immediate), 7);
+        assert_eq!(count_texture_binds(&items, &batched), 4);
+    }
+
+    #[test]
+    fn overlapping_draws_keep_their_order() {
+        let items = [
+            item(0, "grass.png", 0.0),
+            item(0, "rock.png", 10.0),
+            // Overlaps the rock, so has to stay drawn over it
+            item(0, "grass.png", 12.0),
+            item(0, "grass.png", 30.0),
+        ];
+
+        assert_eq!(batch_draws(&items), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn untextured_draws_are_not_grouped() {
+        let mut items = [
+            item(0, "grass.png", 0.0),
+            item(0, "rock.png", 10.0),
+            item(0, "grass.png", 20.0),
+        ];
+        items[0].texture = None;
+        items[2].texture = None;
+
+        assert_eq!(batch_draws(&items), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn draws_with_unknown_bounds_block_reordering() {
+        let mut items = [
+            item(0, "grass.png", 0.0),
+            item(0, "rock.png", 10.0),
+            item(0, "grass.png", 20.0),
+        ];
+        items[1].bounds = None;
+
+        assert_eq!(batch_draws(&items), vec![0, 1, 2]);
+    }
+}
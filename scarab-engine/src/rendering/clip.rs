@@ -0,0 +1,120 @@
+use graphics::{Context, Viewport};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A screen-space rectangle that rendering is clipped to.
+/// The rectangle is `[x, y, w, h]` in points, measured from the top left of the window,
+/// the same space as [crate::rendering::Camera::gutter_rects]
+pub struct ClipRect {
+    rect: [f64; 4],
+}
+
+impl ClipRect {
+    /// Creates a clip rectangle from `[x, y, w, h]` in points.
+    /// Negative widths or heights are treated as 0
+    pub fn new([x, y, w, h]: [f64; 4]) -> Self {
+        Self {
+            rect: [x, y, f64::max(w, 0.0), f64::max(h, 0.0)],
+        }
+    }
+
+    /// The clipped area as `[x, y, w, h]` in points
+    pub fn rect(&self) -> [f64; 4] {
+        self.rect
+    }
+
+    /// The area that is inside both `self` and `other`.
+    /// Has 0 width and/or height when they don't overlap
+    pub fn intersect(&self, other: &Self) -> Self {
+        let left = f64::max(self.rect[0], other.rect[0]);
+        let top = f64::max(self.rect[1], other.rect[1]);
+        let right = f64::min(self.rect[0] + self.rect[2], other.rect[0] + other.rect[2]);
+        let bottom = f64::min(self.rect[1] + self.rect[3], other.rect[1] + other.rect[3]);
+        Self::new([left, top, right - left, bottom - top])
+    }
+
+    /// Converts the clip rectangle to a scissor rectangle in framebuffer pixels,
+    /// taking into account any difference between the window size and the framebuffer size
+    /// (i.e. on high DPI displays)
+    pub fn to_scissor(&self, viewport: Option<&Viewport>) -> [u32; 4] {
+        let [scale_x, scale_y] = viewport.map_or([1.0, 1.0], |v| {
+            [
+                v.draw_size[0] as f64 / v.window_size[0],
+                v.draw_size[1] as f64 / v.window_size[1],
+            ]
+        });
+
+        let left = (self.rect[0] * scale_x).floor().max(0.0);
+        let top = (self.rect[1] * scale_y).floor().max(0.0);
+        let right = ((self.rect[0] + self.rect[2]) * scale_x).ceil().max(left);
+        let bottom = ((self.rect[1] + self.rect[3]) * scale_y).ceil().max(top);
+
+        [
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        ]
+    }
+
+    /// Clips everything drawn with the returned context to this rectangle.
+    /// If `ctx` is already clipped, the result is clipped to the intersection of the two
+    /// so nested clips only ever shrink the drawn area
+    pub fn apply(&self, ctx: Context) -> Context {
+        let scissor = self.to_scissor(ctx.viewport.as_ref());
+        let scissor = match ctx.draw_state.scissor {
+            Some(outer) => intersect_scissors(outer, scissor),
+            None => scissor,
+        };
+
+        let mut ctx = ctx;
+        ctx.draw_state.scissor = Some(scissor);
+        ctx
+    }
+}
+
+fn intersect_scissors(a: [u32; 4], b: [u32; 4]) -> [u32; 4] {
+    let left = u32::max(a[0], b[0]);
+    let top = u32::max(a[1], b[1]);
+    let right = u32::max(u32::min(a[0] + a[2], b[0] + b[2]), left);
+    let bottom = u32::max(u32::min(a[1] + a[3], b[1] + b[3]), top);
+    [left, top, right - left, bottom - top]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_clips_intersect() {
+        let outer = ClipRect::new([0.0, 0.0, 50.0, 50.0]);
+        let inner = ClipRect::new([25.0, 10.0, 50.0, 10.0]);
+
+        assert_eq!(outer.intersect(&inner).rect(), [25.0, 10.0, 25.0, 10.0]);
+        assert_eq!(inner.intersect(&outer), outer.intersect(&inner));
+
+        let ctx = inner.apply(outer.apply(Context::new()));
+        assert_eq!(ctx.draw_state.scissor, Some([25, 10, 25, 10]));
+
+        let disjoint = ClipRect::new([100.0, 100.0, 10.0, 10.0]);
+        let ctx = disjoint.apply(outer.apply(Context::new()));
+        let scissor = ctx.draw_state.scissor.unwrap();
+        assert_eq!(scissor[2] * scissor[3], 0);
+    }
+
+    #[test]
+    fn scissor_scales_with_viewport() {
+        let clip = ClipRect::new([10.0, 20.0, 30.0, 40.0]);
+        let viewport = Viewport {
+            rect: [0, 0, 400, 200],
+            draw_size: [400, 200],
+            window_size: [200.0, 100.0],
+        };
+
+        assert_eq!(clip.to_scissor(None), [10, 20, 30, 40]);
+        assert_eq!(clip.to_scissor(Some(&viewport)), [20, 40, 60, 80]);
+
+        let ctx = clip.apply(Context::new_viewport(viewport));
+        assert_eq!(ctx.draw_state.scissor, Some([20, 40, 60, 80]));
+    }
+}
@@ -1,7 +1,13 @@
-use graphics::types::Scalar;
+use graphics::{
+    types::{Color, Scalar},
+    Context,
+};
+use opengl_graphics::GlGraphics;
 use serde::{Deserialize, Serialize};
 use shapes::Point;
 
+use crate::rendering::{anchor::Anchor, Camera};
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 /// Determines the position of a progress bar when inset in a larger box.
 /// e.g. When making an [inset_left_to_right] `Normal` pushes the y coordinate *down* from the top border while `Inverse` pushes the y coordinate *up* from the bottom border
@@ -144,10 +150,50 @@ pub fn inset_bottom_to_top(
     )
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A progress bar drawn in screen space (i.e. a HUD health bar) that fills in from left to right.
+/// It's placed with [Camera::anchored_rect], so it stays within the camera's safe rect and is scaled with the game
+pub struct AnchoredProgressBar {
+    /// The point of the safe rect that the bar is attached to
+    pub anchor: Anchor,
+    /// The size (in pixels) of the whole bar, including its border
+    pub size: [Scalar; 2],
+    /// How far (in pixels) the bar is moved in from its anchor, see [Anchor::place]
+    pub offset: [Scalar; 2],
+    /// The thickness (in pixels) of the border around the filled portion
+    pub border_size: Scalar,
+    /// The color of the border and the unfilled portion
+    pub background_color: Color,
+    /// The color of the filled portion
+    pub fill_color: Color,
+}
+
+impl AnchoredProgressBar {
+    /// The screen rectangles (in points) of the whole bar and of its portion filled to `fraction`
+    pub fn rects(&self, camera: &Camera, fraction: Scalar) -> ([Scalar; 4], [Scalar; 4]) {
+        let outer = camera.anchored_rect(self.anchor, self.size, self.offset);
+        let fill = inset_left_to_right(
+            &outer,
+            self.border_size * camera.points_per_pixel(),
+            1.0,
+            fraction.clamp(0.0, 1.0),
+            InsetPosition::Normal(0.0),
+        );
+        (outer, fill)
+    }
+
+    /// Draws the bar filled to `fraction`
+    pub fn render(&self, fraction: Scalar, camera: &Camera, ctx: Context, gl: &mut GlGraphics) {
+        let (outer, fill) = self.rects(camera, fraction);
+        graphics::rectangle(self.background_color, outer, ctx.transform, gl);
+        graphics::rectangle(self.fill_color, fill, ctx.transform, gl);
+    }
+}
+
 #[cfg(test)]
 mod test {
 
-    use crate::types::Axis;
+    use crate::{rendering::anchor::SafeArea, types::physbox::PhysBox, types::Axis};
 
     use super::*;
 
@@ -541,4 +587,29 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn anchored_bar_stays_within_the_safe_area() {
+        // The window is wider than the camera, so there are 50 point bars on the sides
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap(),
+            [300.0, 200.0],
+        );
+        let bar = AnchoredProgressBar {
+            anchor: Anchor::TopLeft,
+            size: [20.0, 4.0],
+            offset: [2.0, 2.0],
+            border_size: 1.0,
+            background_color: [0.0, 0.0, 0.0, 1.0],
+            fill_color: [1.0, 0.0, 0.0, 1.0],
+        };
+
+        let (outer, _fill) = bar.rects(&camera, 1.0);
+        assert_eq!(outer, [54.0, 4.0, 40.0, 8.0]);
+
+        camera.set_safe_area(SafeArea::uniform(10.0));
+        let (outer, fill) = bar.rects(&camera, 0.5);
+        assert_eq!(outer, [54.0, 14.0, 40.0, 8.0]);
+        assert_eq!(fill, [56.0, 16.0, 18.0, 4.0]);
+    }
 }
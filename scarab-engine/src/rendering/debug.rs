@@ -42,6 +42,13 @@ impl<'a, V, D> From<(V, D)> for StandardAndDebugView<V, D> {
     }
 }
 
+impl<V, D> StandardAndDebugView<V, D> {
+    /// The view used when rendering without debug info
+    pub fn standard_view(&self) -> &V {
+        &self.standard_view
+    }
+}
+
 impl<V, D> View for StandardAndDebugView<V, D>
 where
     V: View,
@@ -0,0 +1,364 @@
+use graphics::{draw_state::Blend, types::Color, Context, Graphics, Rectangle};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A tint applied to every pixel of a rendered frame, i.e. for a day-night cycle or to flash the
+/// screen when the player takes damage. Each channel becomes `color * multiply + add`, saturating at 1.
+///
+/// This is an overlay approximation of a real grade: there's no offscreen render target to
+/// post-process, so rather than grading the finished frame in a final blit, the grade is drawn as
+/// rectangles over what's already been rendered, using the multiply and add blend modes.
+/// Additive blending can only brighten, so negative `add` channels are treated as 0; darken with
+/// `multiply` instead. Anything drawn after the grade isn't graded
+pub struct ColorGrade {
+    /// Scales each channel of the frame's colors
+    pub multiply: Color,
+    /// Is added to each channel of the frame's colors after multiplying.
+    /// Negative channels are clamped to 0 when rendering
+    pub add: Color,
+}
+
+impl ColorGrade {
+    /// The grade that leaves every color unchanged
+    pub const IDENTITY: Self = Self {
+        multiply: [1.0, 1.0, 1.0, 1.0],
+        add: [0.0, 0.0, 0.0, 0.0],
+    };
+
+    /// Creates a grade that only scales the frame's colors
+    pub fn tint(multiply: Color) -> Self {
+        Self {
+            multiply,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Whether the grade leaves every color unchanged, in which case it isn't rendered at all
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    /// The grade `t` of the way from this grade to `other`
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp = |from: Color, to: Color| {
+            let mut color = from;
+            for (i, channel) in color.iter_mut().enumerate() {
+                *channel += (to[i] - from[i]) * t;
+            }
+            color
+        };
+
+        Self {
+            multiply: lerp(self.multiply, other.multiply),
+            add: lerp(self.add, other.add),
+        }
+    }
+
+    /// The `add` color that's actually rendered, with negative channels clamped to 0
+    pub fn rendered_add(&self) -> Color {
+        self.add.map(|channel| channel.max(0.0))
+    }
+
+    /// Applies the grade to everything already drawn within `rect` (in points) by drawing
+    /// a rectangle of `multiply` with multiply blending, then one of the
+    /// [rendered add](ColorGrade::rendered_add) with additive blending
+    pub fn render<G: Graphics>(&self, rect: [f64; 4], ctx: Context, g: &mut G) {
+        if self.is_identity() {
+            return;
+        }
+
+        Rectangle::new(self.multiply).draw(
+            rect,
+            &ctx.draw_state.blend(Blend::Multiply),
+            ctx.transform,
+            g,
+        );
+        let add = self.rendered_add();
+        if add != Self::IDENTITY.add {
+            Rectangle::new(add).draw(rect, &ctx.draw_state.blend(Blend::Add), ctx.transform, g);
+        }
+    }
+}
+
+impl Default for ColorGrade {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// How a transition's progress speeds up and slows down over its duration
+pub enum Easing {
+    /// Progresses at a constant rate
+    #[default]
+    Linear,
+    /// Starts slow and speeds up
+    EaseIn,
+    /// Starts fast and slows down
+    EaseOut,
+    /// Starts and ends slow
+    EaseInOut,
+}
+
+impl Easing {
+    /// The eased progress for the fraction `t` of the duration that has passed
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct GradeTransition {
+    from: ColorGrade,
+    to: ColorGrade,
+    /// The game time (in seconds) the transition takes
+    duration: f64,
+    /// The game time (in seconds) since the transition started
+    elapsed: f64,
+    easing: Easing,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// The color grade a camera applies to each frame, which can be changed gradually over game time
+pub struct ColorGrading {
+    grade: ColorGrade,
+    transition: Option<GradeTransition>,
+    /// Whether the grade also tints the bars along the edges of the window
+    affects_gutters: bool,
+}
+
+impl ColorGrading {
+    /// The grade currently applied to frames
+    pub fn grade(&self) -> ColorGrade {
+        self.grade
+    }
+
+    /// Immediately switches to the given grade, cancelling any transition
+    pub fn set_grade(&mut self, grade: ColorGrade) {
+        self.grade = grade;
+        self.transition = None;
+    }
+
+    /// Gradually changes from the current grade to `to` over `duration` seconds of game time.
+    /// Starting a transition part way through another starts from the grade shown at the time
+    pub fn transition_to(&mut self, to: ColorGrade, duration: f64, easing: Easing) {
+        self.transition = Some(GradeTransition {
+            from: self.grade,
+            to,
+            duration,
+            elapsed: 0.0,
+            easing,
+        });
+        self.update(0.0);
+    }
+
+    /// Whether the grade is still changing
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Progresses the current transition by `dt` seconds of game time
+    pub fn update(&mut self, dt: f64) {
+        if let Some(transition) = &mut self.transition {
+            transition.elapsed += dt;
+            if transition.elapsed >= transition.duration {
+                self.grade = transition.to;
+                self.transition = None;
+            } else {
+                let t = transition
+                    .easing
+                    .apply((transition.elapsed / transition.duration) as f32);
+                self.grade = transition.from.lerp(&transition.to, t);
+            }
+        }
+    }
+
+    /// Sets whether the grade also tints the bars along the edges of the window
+    pub fn set_affects_gutters(&mut self, affects_gutters: bool) {
+        self.affects_gutters = affects_gutters;
+    }
+
+    /// Whether the grade also tints the bars along the edges of the window
+    pub fn affects_gutters(&self) -> bool {
+        self.affects_gutters
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use graphics::{DrawState, ImageSize};
+
+    use super::*;
+
+    const SKY: Color = [0.25, 0.5, 0.75, 1.0];
+    const WHITE: Color = [1.0, 1.0, 1.0, 1.0];
+
+    struct NoTexture;
+
+    impl ImageSize for NoTexture {
+        fn get_size(&self) -> (u32, u32) {
+            (0, 0)
+        }
+    }
+
+    /// A graphics backend that records the blend mode and color of each untextured draw
+    #[derive(Default)]
+    struct RecordingGraphics {
+        draws: Vec<(Option<Blend>, Color)>,
+    }
+
+    impl Graphics for RecordingGraphics {
+        type Texture = NoTexture;
+
+        fn clear_color(&mut self, _color: Color) {}
+
+        fn clear_stencil(&mut self, _value: u8) {}
+
+        fn tri_list<F>(&mut self, draw_state: &DrawState, color: &[f32; 4], _f: F)
+        where
+            F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+        {
+            self.draws.push((draw_state.blend, *color));
+        }
+
+        fn tri_list_c<F>(&mut self, _draw_state: &DrawState, _f: F)
+        where
+            F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+        {
+        }
+
+        fn tri_list_uv<F>(
+            &mut self,
+            _draw_state: &DrawState,
+            _color: &[f32; 4],
+            _texture: &Self::Texture,
+            _f: F,
+        ) where
+            F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+        {
+        }
+
+        fn tri_list_uv_c<F>(&mut self, _draw_state: &DrawState, _texture: &Self::Texture, _f: F)
+        where
+            F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+        {
+        }
+    }
+
+    /// Renders the grade with the recording backend
+    fn record(grade: &ColorGrade) -> Vec<(Option<Blend>, Color)> {
+        let mut g = RecordingGraphics::default();
+        grade.render([0.0, 0.0, 10.0, 10.0], Context::new(), &mut g);
+        g.draws
+    }
+
+    /// A model of the GL blend equations for the recorded draws over a pixel of `dst`:
+    /// multiply is `dst * src`, add is `dst + src`, and the framebuffer saturates at 1
+    fn composite(dst: Color, draws: &[(Option<Blend>, Color)]) -> Color {
+        draws.iter().fold(dst, |mut dst, (blend, src)| {
+            for (i, channel) in dst.iter_mut().enumerate() {
+                *channel = match blend {
+                    Some(Blend::Multiply) => *channel * src[i],
+                    Some(Blend::Add) => *channel + src[i],
+                    _ => panic!("the grade should only draw with multiply or add blending"),
+                }
+                .clamp(0.0, 1.0);
+            }
+            dst
+        })
+    }
+
+    #[test]
+    fn identity_grade_draws_nothing() {
+        let grade = ColorGrade::default();
+
+        assert!(grade.is_identity());
+        assert!(record(&grade).is_empty());
+    }
+
+    #[test]
+    fn tint_only_draws_the_multiply_pass() {
+        let grade = ColorGrade::tint([0.5, 0.5, 1.0, 1.0]);
+
+        assert_eq!(
+            record(&grade),
+            vec![(Some(Blend::Multiply), [0.5, 0.5, 1.0, 1.0])]
+        );
+    }
+
+    #[test]
+    fn red_tint_shifts_colors_towards_red() {
+        let grade = ColorGrade {
+            multiply: [1.0, 0.5, 0.5, 1.0],
+            add: [0.25, 0.0, 0.0, 0.0],
+        };
+
+        let draws = record(&grade);
+        // Multiplies first, then adds
+        assert_eq!(
+            draws,
+            vec![
+                (Some(Blend::Multiply), grade.multiply),
+                (Some(Blend::Add), grade.add),
+            ]
+        );
+        assert_eq!(composite(SKY, &draws), [0.5, 0.25, 0.375, 1.0]);
+        // Channels saturate instead of overflowing
+        assert_eq!(composite(WHITE, &draws), [1.0, 0.5, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn negative_add_channels_are_clamped() {
+        let grade = ColorGrade {
+            multiply: [0.5, 0.5, 0.5, 1.0],
+            add: [-0.25, 0.25, 0.0, 0.0],
+        };
+
+        let draws = record(&grade);
+        assert_eq!(
+            draws,
+            vec![
+                (Some(Blend::Multiply), grade.multiply),
+                (Some(Blend::Add), [0.0, 0.25, 0.0, 0.0]),
+            ]
+        );
+        assert_eq!(composite(SKY, &draws), [0.125, 0.5, 0.375, 1.0]);
+
+        // Only darkening the add does nothing, so the add pass is skipped
+        let grade = ColorGrade {
+            add: [-0.25, -0.25, -0.25, 0.0],
+            ..grade
+        };
+        assert_eq!(
+            record(&grade),
+            vec![(Some(Blend::Multiply), grade.multiply)]
+        );
+    }
+
+    #[test]
+    fn transitions_ease_between_grades() {
+        let night = ColorGrade::tint([0.5, 0.5, 0.75, 1.0]);
+        let mut grading = ColorGrading::default();
+
+        grading.transition_to(night, 2.0, Easing::EaseIn);
+        assert_eq!(grading.grade(), ColorGrade::IDENTITY);
+
+        grading.update(1.0);
+        // A quarter of the way with ease in at half the duration
+        assert_eq!(
+            grading.grade(),
+            ColorGrade::tint([0.875, 0.875, 0.9375, 1.0])
+        );
+        assert!(grading.is_transitioning());
+
+        grading.update(1.5);
+        assert_eq!(grading.grade(), night);
+        assert!(!grading.is_transitioning());
+    }
+}
@@ -8,19 +8,35 @@ use piston::RenderArgs;
 use serde::{Deserialize, Serialize};
 use shapes::Point;
 
-use self::registry::TextureRegistry;
+use self::{
+    anchor::{Anchor, SafeArea},
+    grade::ColorGrading,
+    registry::TextureRegistry,
+    sprite::SpriteView,
+    time::{system_clock, SharedTimeSource},
+};
 use crate::{error::RenderResult, types::physbox::PhysBox};
 
+/// Anchoring UI to the safe area and boxes to their sprites
+pub mod anchor;
+/// Grouping draws to reduce texture binds
+pub mod batch;
+/// Clipping rendering to areas of the screen
+pub mod clip;
 #[cfg(feature = "component-rendering")]
 /// For rendering generic reusable components
 pub mod components;
 #[cfg(feature = "debug-rendering")]
 /// Rendering additional debugging info
 pub mod debug;
+/// Tinting whole frames
+pub mod grade;
 /// Rendering registries
 pub mod registry;
 /// Specifically for rendering sprites
 pub mod sprite;
+/// Clocks for driving animations
+pub mod time;
 
 /// A trait for types that control how another type is rendered
 pub trait View {
@@ -39,6 +55,21 @@ pub trait View {
     ) -> RenderResult<()>;
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Controls what is drawn in the bars along the edges of the window that the camera doesn't fill
+pub enum GutterFill {
+    /// Fills the bars with a solid color
+    Color(Color),
+    /// Tiles a sprite across the bars, scaled to match the camera's pixel size
+    Sprite(SpriteView),
+}
+
+impl Default for GutterFill {
+    fn default() -> Self {
+        Self::Color([0.0, 0.0, 0.0, 1.0])
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Controls how the pixel art is rendered and maintained through play
 ///
@@ -56,6 +87,19 @@ pub struct Camera {
     vertical_bar_width: f64,
     /// The width of each horizontal bar necessary to fill up the window (in points)
     horizontal_bar_height: f64,
+    /// What to draw in the vertical/horizontal bars
+    #[serde(default)]
+    gutter_fill: GutterFill,
+    /// The margins along the edges of the window that screen-space UI is kept out of
+    #[serde(default)]
+    safe_area: SafeArea,
+    /// The tint applied over each rendered frame
+    #[serde(default)]
+    color_grading: ColorGrading,
+    /// The clock that everything rendered with the camera is animated by
+    #[serde(skip)]
+    #[serde(default = "system_clock")]
+    time_source: SharedTimeSource,
 }
 
 impl Camera {
@@ -67,6 +111,10 @@ impl Camera {
             window_size: window_size.clone(),
             vertical_bar_width: 0.0,
             horizontal_bar_height: 0.0,
+            gutter_fill: GutterFill::default(),
+            safe_area: SafeArea::default(),
+            color_grading: ColorGrading::default(),
+            time_source: system_clock(),
         };
 
         s.set_window_size(&window_size);
@@ -101,6 +149,25 @@ impl Camera {
         ctx.transform.trans(top_left_scaled.x, top_left_scaled.y)
     }
 
+    /// Converts a point on the screen (i.e. a mouse position) to world coordinates.
+    /// Returns `None` when the point is in one of the bars along the edges of the window
+    pub fn screen_to_world(&self, screen_pos: Point) -> Option<Point> {
+        if screen_pos.x < self.vertical_bar_width
+            || screen_pos.x >= self.window_size[0] - self.vertical_bar_width
+            || screen_pos.y < self.horizontal_bar_height
+            || screen_pos.y >= self.window_size[1] - self.horizontal_bar_height
+        {
+            return None;
+        }
+
+        Some(Point {
+            x: (screen_pos.x - self.vertical_bar_width) / self.points_per_pixel
+                + self.physbox.left_x(),
+            y: (screen_pos.y - self.horizontal_bar_height) / self.points_per_pixel
+                + self.physbox.top_y(),
+        })
+    }
+
     /// The actual screen "points" per pixel-art-pixel for the camera
     pub fn points_per_pixel(&self) -> f64 {
         self.points_per_pixel
@@ -128,6 +195,134 @@ impl Camera {
         }
     }
 
+    /// Sets what is drawn in the bars along the edges of the window
+    pub fn set_gutter_fill(&mut self, gutter_fill: GutterFill) {
+        self.gutter_fill = gutter_fill;
+    }
+
+    /// What is drawn in the bars along the edges of the window
+    pub fn gutter_fill(&self) -> &GutterFill {
+        &self.gutter_fill
+    }
+
+    /// Sets the margins along the edges of the window that screen-space UI is kept out of
+    pub fn set_safe_area(&mut self, safe_area: SafeArea) {
+        self.safe_area = safe_area;
+    }
+
+    /// The margins along the edges of the window that screen-space UI is kept out of
+    pub fn safe_area(&self) -> &SafeArea {
+        &self.safe_area
+    }
+
+    /// The screen rectangle (in points) that screen-space UI should be drawn within.
+    /// This is the part of the window the camera draws to, less any of the safe area's margins
+    /// that reach into it
+    pub fn safe_rect(&self) -> [f64; 4] {
+        let [w_w, h_w] = self.window_size;
+        let left = f64::max(self.vertical_bar_width, self.safe_area.left);
+        let top = f64::max(self.horizontal_bar_height, self.safe_area.top);
+        let right = f64::min(w_w - self.vertical_bar_width, w_w - self.safe_area.right);
+        let bottom = f64::min(
+            h_w - self.horizontal_bar_height,
+            h_w - self.safe_area.bottom,
+        );
+
+        [
+            left,
+            top,
+            f64::max(right - left, 0.0),
+            f64::max(bottom - top, 0.0),
+        ]
+    }
+
+    /// The screen rectangle (in points) for a UI element anchored within the [safe rect](Camera::safe_rect).
+    /// `size` and `offset` are in pixels, so UI is scaled the same as the rest of the game.
+    /// See [Anchor::place] for how `offset` is applied
+    pub fn anchored_rect(&self, anchor: Anchor, size: [f64; 2], offset: [f64; 2]) -> [f64; 4] {
+        let scale = self.points_per_pixel;
+        anchor.place(
+            self.safe_rect(),
+            [size[0] * scale, size[1] * scale],
+            [offset[0] * scale, offset[1] * scale],
+        )
+    }
+
+    /// The screen rectangles (in points) of the bars along the edges of the window.
+    /// Bars with no area are left out
+    pub fn gutter_rects(&self) -> Vec<[f64; 4]> {
+        let [w_w, h_w] = self.window_size;
+        let mut rects = Vec::with_capacity(4);
+
+        if self.vertical_bar_width > 0.0 {
+            rects.push([0.0, 0.0, self.vertical_bar_width, h_w]);
+            rects.push([
+                w_w - self.vertical_bar_width,
+                0.0,
+                self.vertical_bar_width,
+                h_w,
+            ]);
+        }
+
+        if self.horizontal_bar_height > 0.0 {
+            rects.push([0.0, 0.0, w_w, self.horizontal_bar_height]);
+            rects.push([
+                0.0,
+                h_w - self.horizontal_bar_height,
+                w_w,
+                self.horizontal_bar_height,
+            ]);
+        }
+
+        rects
+    }
+
+    /// Sets the clock that every animation rendered with the camera reads the current time from,
+    /// i.e. a [MockClock](time::MockClock) for deterministic replays and tests
+    pub fn set_time_source(&mut self, time_source: SharedTimeSource) {
+        self.time_source = time_source;
+    }
+
+    /// The clock that every animation rendered with the camera reads the current time from
+    pub fn time_source(&self) -> &SharedTimeSource {
+        &self.time_source
+    }
+
+    /// The color grade applied over each rendered frame
+    pub fn color_grading(&self) -> &ColorGrading {
+        &self.color_grading
+    }
+
+    /// Mutable access to the color grade applied over each rendered frame, i.e. to start a transition
+    pub fn color_grading_mut(&mut self) -> &mut ColorGrading {
+        &mut self.color_grading
+    }
+
+    /// The screen rectangle (in points) that the color grade is applied to.
+    /// This is the whole window if the grade [affects the gutters](ColorGrading::affects_gutters),
+    /// otherwise just the part of the window the camera draws to
+    pub fn color_grade_rect(&self) -> [f64; 4] {
+        let [w_w, h_w] = self.window_size;
+        if self.color_grading.affects_gutters() {
+            [0.0, 0.0, w_w, h_w]
+        } else {
+            [
+                self.vertical_bar_width,
+                self.horizontal_bar_height,
+                w_w - 2.0 * self.vertical_bar_width,
+                h_w - 2.0 * self.horizontal_bar_height,
+            ]
+        }
+    }
+
+    /// Applies the color grade over everything already rendered this frame.
+    /// Should be called last, after the gutters are rendered
+    pub fn render_color_grade(&self, _args: &RenderArgs, ctx: Context, gl: &mut GlGraphics) {
+        self.color_grading
+            .grade()
+            .render(self.color_grade_rect(), ctx, gl);
+    }
+
     /// Renders the (usually black) bars along the edges of the screen so that only
     /// the camera's allotted pixels are rendered
     pub fn render_gutters(
@@ -137,42 +332,159 @@ impl Camera {
         ctx: Context,
         gl: &mut GlGraphics,
     ) {
-        graphics::rectangle(
-            color,
-            [0.0, 0.0, self.vertical_bar_width, self.window_size[1]],
-            ctx.transform,
-            gl,
+        for rect in self.gutter_rects() {
+            graphics::rectangle(color, rect, ctx.transform, gl);
+        }
+    }
+
+    /// Renders the bars along the edges of the screen with the camera's [GutterFill]
+    pub fn render_gutter_fill(
+        &self,
+        args: &RenderArgs,
+        ctx: Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut GlGraphics,
+    ) {
+        match &self.gutter_fill {
+            GutterFill::Color(color) => self.render_gutters(*color, args, ctx, gl),
+            GutterFill::Sprite(sprite) => {
+                for rect in self.gutter_rects() {
+                    sprite.render_tiled(rect, self.points_per_pixel, ctx, texture_registry, gl);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use super::*;
+    use crate::rendering::time::{MockClock, TimeSource};
+
+    const RED: Color = [1.0, 0.0, 0.0, 1.0];
+
+    fn contains(rect: &[f64; 4], point: Point) -> bool {
+        point.x >= rect[0]
+            && point.x < rect[0] + rect[2]
+            && point.y >= rect[1]
+            && point.y < rect[1] + rect[3]
+    }
+
+    fn create_pillarbox_camera() -> Camera {
+        // The window is wider than the camera's aspect ratio, so there are bars on the sides
+        let mut camera = Camera::new(
+            PhysBox::new([0.0, 0.0, 100.0, 100.0]).unwrap(),
+            [300.0, 200.0],
         );
+        camera.set_gutter_fill(GutterFill::Color(RED));
+        camera
+    }
 
-        graphics::rectangle(
-            color,
-            [
-                self.window_size[0] - self.vertical_bar_width,
-                0.0,
-                self.vertical_bar_width,
-                self.window_size[1],
-            ],
-            ctx.transform,
-            gl,
+    #[test]
+    fn pillarbox_side_margins_use_gutter_fill() {
+        let camera = create_pillarbox_camera();
+
+        assert_eq!(
+            camera.gutter_rects(),
+            vec![[0.0, 0.0, 50.0, 200.0], [250.0, 0.0, 50.0, 200.0]]
         );
+        assert!(matches!(camera.gutter_fill(), GutterFill::Color(c) if *c == RED));
+
+        let left_margin = Point { x: 10.0, y: 100.0 };
+        let right_margin = Point { x: 290.0, y: 100.0 };
+        let center = Point { x: 150.0, y: 100.0 };
+        let rects = camera.gutter_rects();
+        assert!(rects.iter().any(|r| contains(r, left_margin)));
+        assert!(rects.iter().any(|r| contains(r, right_margin)));
+        assert!(!rects.iter().any(|r| contains(r, center)));
+    }
+
+    #[test]
+    fn screen_to_world_excludes_gutters() {
+        let camera = create_pillarbox_camera();
 
-        graphics::rectangle(
-            color,
-            [0.0, 0.0, self.window_size[0], self.horizontal_bar_height],
-            ctx.transform,
-            gl,
+        assert_eq!(camera.screen_to_world(Point { x: 10.0, y: 100.0 }), None);
+        assert_eq!(camera.screen_to_world(Point { x: 250.0, y: 100.0 }), None);
+        assert_eq!(
+            camera.screen_to_world(Point { x: 50.0, y: 0.0 }),
+            Some(Point { x: 0.0, y: 0.0 })
         );
+        assert_eq!(
+            camera.screen_to_world(Point { x: 150.0, y: 100.0 }),
+            Some(Point { x: 50.0, y: 50.0 })
+        );
+    }
 
-        graphics::rectangle(
-            color,
-            [
-                0.0,
-                self.window_size[1] - self.horizontal_bar_height,
-                self.window_size[0],
-                self.horizontal_bar_height,
-            ],
-            ctx.transform,
-            gl,
+    #[test]
+    fn resizing_updates_gutters() {
+        let mut camera = create_pillarbox_camera();
+
+        // Now the window is taller than the camera's aspect ratio
+        camera.set_window_size(&[200.0, 400.0]);
+
+        assert_eq!(
+            camera.gutter_rects(),
+            vec![[0.0, 0.0, 200.0, 100.0], [0.0, 300.0, 200.0, 100.0]]
         );
+        assert!(camera
+            .screen_to_world(Point { x: 10.0, y: 100.0 })
+            .is_some());
+        assert_eq!(camera.screen_to_world(Point { x: 10.0, y: 50.0 }), None);
+        assert_eq!(camera.screen_to_world(Point { x: 10.0, y: 350.0 }), None);
+    }
+
+    #[test]
+    fn top_right_anchor_is_offset_by_safe_area() {
+        let mut camera = create_pillarbox_camera();
+        let (size, offset) = ([10.0, 5.0], [2.0, 2.0]);
+
+        // Without a safe area, UI is anchored to the corner of the camera's view
+        assert_eq!(camera.safe_rect(), [50.0, 0.0, 200.0, 200.0]);
+        assert_eq!(
+            camera.anchored_rect(Anchor::TopRight, size, offset),
+            [226.0, 4.0, 20.0, 10.0]
+        );
+
+        // The right margin reaches past the pillarbox bar, the top one is into the view
+        camera.set_safe_area(SafeArea {
+            top: 10.0,
+            right: 60.0,
+            ..Default::default()
+        });
+        assert_eq!(camera.safe_rect(), [50.0, 10.0, 190.0, 190.0]);
+        assert_eq!(
+            camera.anchored_rect(Anchor::TopRight, size, offset),
+            [216.0, 14.0, 20.0, 10.0]
+        );
+
+        // Margins within the bars don't move anything
+        camera.set_safe_area(SafeArea {
+            left: 30.0,
+            ..Default::default()
+        });
+        assert_eq!(camera.safe_rect(), [50.0, 0.0, 200.0, 200.0]);
+    }
+
+    #[test]
+    fn color_grade_covers_gutters_only_when_flagged() {
+        let mut camera = create_pillarbox_camera();
+
+        assert_eq!(camera.color_grade_rect(), [50.0, 0.0, 200.0, 200.0]);
+
+        camera.color_grading_mut().set_affects_gutters(true);
+        assert_eq!(camera.color_grade_rect(), [0.0, 0.0, 300.0, 200.0]);
+    }
+
+    #[test]
+    fn camera_clock_is_shared_with_everything_it_renders() {
+        let mut camera = create_pillarbox_camera();
+        let clock = Arc::new(MockClock::new());
+        camera.set_time_source(clock.clone());
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(camera.time_source().now(), clock.now());
+        assert_eq!(camera.clone().time_source().now(), clock.now());
     }
 }
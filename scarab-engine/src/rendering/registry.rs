@@ -2,6 +2,7 @@
 use std::{collections::HashMap, path::PathBuf};
 
 use derivative::Derivative;
+use image::RgbaImage;
 use opengl_graphics::{Filter, Texture, TextureSettings};
 use serde::{Deserialize, Serialize};
 
@@ -101,6 +102,14 @@ impl TextureRegistry {
         Ok(self.textures.insert(path, texture))
     }
 
+    /// Reads the pixels of the image at the path, i.e. for inspecting a texture's contents.
+    /// The image isn't kept in the registry
+    pub fn load_image(&self, path: &PathBuf) -> RenderResult<RgbaImage> {
+        image::open(self.assets_path.join(path))
+            .map(|image| image.into_rgba8())
+            .map_err(|e| RenderError::CouldNotLoadTexture(path.clone(), e.to_string()))
+    }
+
     fn load_inner(path: &PathBuf) -> RenderResult<Texture> {
         let settings = TextureSettings::new().filter(Filter::Nearest);
         Texture::from_path(path, &settings)
@@ -0,0 +1,53 @@
+use image::RgbaImage;
+
+/// The smallest rectangle `[x, y, w, h]` containing every pixel with any opacity within the
+/// `src` rectangle of the image. The position is relative to the top left of `src`.
+/// Returns `None` if every pixel in `src` is fully transparent
+pub fn opaque_bounds(image: &RgbaImage, src: [u32; 4]) -> Option<[u32; 4]> {
+    let [src_x, src_y, src_w, src_h] = src;
+    let x_end = u32::min(src_x + src_w, image.width());
+    let y_end = u32::min(src_y + src_h, image.height());
+
+    let mut bounds: Option<[u32; 4]> = None;
+    for y in src_y..y_end {
+        for x in src_x..x_end {
+            if image.get_pixel(x, y)[3] == 0 {
+                continue;
+            }
+
+            let [min_x, min_y, max_x, max_y] = bounds.get_or_insert([x, y, x, y]);
+            *min_x = u32::min(*min_x, x);
+            *min_y = u32::min(*min_y, y);
+            *max_x = u32::max(*max_x, x);
+            *max_y = u32::max(*max_y, y);
+        }
+    }
+
+    bounds.map(|[min_x, min_y, max_x, max_y]| {
+        [
+            min_x - src_x,
+            min_y - src_y,
+            max_x - min_x + 1,
+            max_y - min_y + 1,
+        ]
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn bounds_are_relative_to_the_source_rect() {
+        let mut image = RgbaImage::new(32, 16);
+        image.put_pixel(20, 3, Rgba([0, 0, 0, 1]));
+        image.put_pixel(25, 9, Rgba([255, 255, 255, 255]));
+        // Outside of the source rect
+        image.put_pixel(2, 2, Rgba([255, 255, 255, 255]));
+
+        assert_eq!(opaque_bounds(&image, [16, 0, 16, 16]), Some([4, 3, 6, 7]));
+        assert_eq!(opaque_bounds(&image, [0, 8, 16, 8]), None);
+    }
+}
@@ -1,21 +1,32 @@
 /// Rendering sprites attached to a game object
 use core::{fmt::Debug, marker::PhantomData};
-use std::{collections::HashMap, hash::Hash, path::PathBuf, time::Instant};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
 use derivative::Derivative;
 use graphics::{Image, ImageSize, Transformed};
+use image::RgbaImage;
 use piston::RenderArgs;
 use serde::{Deserialize, Serialize};
 use shapes::{Point, Size};
 
 use self::sprite_serde::ImageDef;
-use super::{registry::TextureRegistry, Camera, View};
+use super::{anchor::Anchor, clip::ClipRect, registry::TextureRegistry, Camera, View};
 use crate::{
     error::{AnimationError, RenderError, RenderResult},
-    types::{physbox::HasBox, Axis},
-    ScarabResult,
+    types::{
+        physbox::{HasBox, PhysBox},
+        Axis,
+    },
+    PhysicsResult, ScarabResult,
 };
 
+/// Finding the visible parts of sprites
+pub mod bounds;
 mod sprite_serde;
 
 #[derive(Derivative, Clone, Serialize, Deserialize)]
@@ -29,6 +40,11 @@ pub struct SpriteView {
     #[serde(with = "ImageDef")]
     image: Image,
     texture_path: PathBuf,
+    #[serde(default)]
+    clip: Option<ClipRect>,
+    /// Moves the sprite on top of `pos` so a fitted box lines up with the current frame's opaque pixels
+    #[serde(skip)]
+    fit_offset: [f64; 2],
 }
 
 impl SpriteView {
@@ -42,9 +58,47 @@ impl SpriteView {
                 .rect([0.0, 0.0, sprite_size.w, sprite_size.h])
                 .src_rect([0.0, 0.0, sprite_size.w, sprite_size.h]),
             texture_path,
+            clip: None,
+            fit_offset: [0.0, 0.0],
         })
     }
 
+    /// The path of the texture the sprite is drawn from
+    pub fn texture_path(&self) -> &Path {
+        &self.texture_path
+    }
+
+    /// Clips the sprite to the given screen rectangle, or stops clipping it if `None`.
+    /// Nests with any clipping already applied to the context it's rendered with
+    pub fn set_clip(&mut self, clip: Option<ClipRect>) {
+        self.clip = clip;
+    }
+
+    /// The area (in world coordinates) the sprite covers when drawn for a game object with the given box
+    pub fn draw_bounds(&self, viewed_box: &PhysBox) -> Option<PhysBox> {
+        let offset = self.draw_offset();
+        PhysBox::new([
+            viewed_box.left_x() - offset.x,
+            viewed_box.top_y() - offset.y,
+            self.sprite_size.w,
+            self.sprite_size.h,
+        ])
+        .ok()
+    }
+
+    /// How far up and left of the viewed game object's box the sprite is drawn
+    fn draw_offset(&self) -> Point {
+        Point {
+            x: self.pos.x + self.fit_offset[0],
+            y: self.pos.y + self.fit_offset[1],
+        }
+    }
+
+    /// The context the sprite is drawn with, clipped to the sprite's clip rectangle if it has one
+    fn clipped_context(&self, ctx: graphics::Context) -> graphics::Context {
+        self.clip.map_or(ctx, |clip| clip.apply(ctx))
+    }
+
     fn set_src_rect_pos(&mut self, new_pos: Point) {
         if let Some(rect) = self.image.source_rectangle.as_mut() {
             rect[0] = new_pos.x;
@@ -52,6 +106,59 @@ impl SpriteView {
         }
     }
 
+    /// Tiles the sprite across the given screen rectangle (in points), scaling each tile by `scale`.
+    /// Tiles along the right and bottom of the rectangle are cut short to stay within it
+    pub(crate) fn render_tiled(
+        &self,
+        rect: [f64; 4],
+        scale: f64,
+        ctx: graphics::Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut opengl_graphics::GlGraphics,
+    ) {
+        let src_pos = self
+            .image
+            .source_rectangle
+            .map_or([0.0, 0.0].into(), |src| [src[0], src[1]].into());
+        self.render_tiled_from(src_pos, rect, scale, ctx, texture_registry, gl)
+    }
+
+    /// Tiles the sprite like [SpriteView::render_tiled], but with the tile's source rectangle at `src_pos`
+    fn render_tiled_from(
+        &self,
+        src_pos: Point,
+        rect: [f64; 4],
+        scale: f64,
+        ctx: graphics::Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut opengl_graphics::GlGraphics,
+    ) {
+        let tile_w = self.sprite_size.w * scale;
+        let tile_h = self.sprite_size.h * scale;
+        if tile_w <= 0.0 || tile_h <= 0.0 {
+            return;
+        }
+
+        let ctx = self.clipped_context(ctx);
+        let texture = texture_registry.get_or_default(&self.texture_path);
+        let Point { x: src_x, y: src_y } = src_pos;
+
+        let mut y = rect[1];
+        while y < rect[1] + rect[3] {
+            let h = f64::min(tile_h, rect[1] + rect[3] - y);
+            let mut x = rect[0];
+            while x < rect[0] + rect[2] {
+                let w = f64::min(tile_w, rect[0] + rect[2] - x);
+                Image::new()
+                    .rect([x, y, w, h])
+                    .src_rect([src_x, src_y, w / scale, h / scale])
+                    .draw(texture, &ctx.draw_state, ctx.transform, gl);
+                x += tile_w;
+            }
+            y += tile_h;
+        }
+    }
+
     fn render<V: HasBox>(
         &mut self,
         viewed: &V,
@@ -61,13 +168,32 @@ impl SpriteView {
         texture_registry: &TextureRegistry,
         gl: &mut opengl_graphics::GlGraphics,
     ) -> RenderResult<()> {
-        if let Some((transform, _rect)) = camera.box_renderables(viewed.get_box(), ctx) {
+        self.render_with_alpha(viewed, camera, ctx, texture_registry, gl, 1.0)
+    }
+
+    /// Renders the sprite with its opacity multiplied by `alpha`
+    fn render_with_alpha<V: HasBox>(
+        &mut self,
+        viewed: &V,
+        camera: &Camera,
+        ctx: graphics::Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut opengl_graphics::GlGraphics,
+        alpha: f32,
+    ) -> RenderResult<()> {
+        let ctx = self.clipped_context(ctx);
+        if let Some((transform, _rect)) = camera.box_renderables(&viewed.get_render_box(), ctx) {
             let scale_factor = camera.points_per_pixel();
             let transform = transform
-                .trans_pos(self.pos * -scale_factor)
+                .trans_pos(self.draw_offset() * -scale_factor)
                 .scale(scale_factor, scale_factor);
 
-            self.image.draw(
+            let image = if alpha < 1.0 {
+                self.image.color([1.0, 1.0, 1.0, alpha])
+            } else {
+                self.image
+            };
+            image.draw(
                 texture_registry.get_or_default(&self.texture_path),
                 &ctx.draw_state,
                 transform,
@@ -92,10 +218,18 @@ pub struct SpriteAnimation {
     milliseconds_per_frame: f64,
     /// The axis within the sprite map that adding to gets to the next frame
     animation_direction: Axis,
-    /// The timestamp at which the last frame was set
+    /// The timestamp at which the last frame was set, or `None` if the animation starts
+    /// the next time it's advanced
+    #[serde(skip)]
+    last_update: Option<Instant>,
+    /// When set, boxes are fit to the opaque pixels of the current frame, keeping this anchor in place
+    #[serde(default)]
+    auto_fit: Option<Anchor>,
+    /// The cached bounds `[x, y, w, h]` of the opaque pixels in each frame, relative to the frame.
+    /// Loaded from the sprite map the first time the animation is rendered with auto fit enabled,
+    /// or the whole frames if the sprite map couldn't be read
     #[serde(skip)]
-    #[serde(default = "Instant::now")]
-    last_update: Instant,
+    frame_bounds: Vec<Option<[f64; 4]>>,
 }
 
 impl SpriteAnimation {
@@ -139,7 +273,9 @@ impl SpriteAnimation {
             frame_num: 0,
             milliseconds_per_frame,
             animation_direction,
-            last_update: Instant::now(),
+            last_update: None,
+            auto_fit: None,
+            frame_bounds: Vec::new(),
         })
     }
 
@@ -151,42 +287,398 @@ impl SpriteAnimation {
             frame_num: 0,
             milliseconds_per_frame: 1000.0,
             animation_direction: Axis::X,
-            last_update: Instant::now(),
+            last_update: None,
+            auto_fit: None,
+            frame_bounds: Vec::new(),
         }
     }
 
-    /// Prepares the animation to be started again.
+    /// The path of the sprite map the animation is drawn from
+    pub fn texture_path(&self) -> &Path {
+        self.sprite.texture_path()
+    }
+
+    /// The area (in world coordinates) the animation covers when drawn for a game object with the given box
+    pub fn draw_bounds(&self, viewed_box: &PhysBox) -> Option<PhysBox> {
+        let fitted = self.fitted_box(viewed_box).ok().flatten();
+        self.sprite
+            .draw_bounds(fitted.as_ref().unwrap_or(viewed_box))
+    }
+
+    /// Clips the animation to the given screen rectangle, or stops clipping it if `None`
+    pub fn set_clip(&mut self, clip: Option<ClipRect>) {
+        self.sprite.set_clip(clip);
+    }
+
+    /// Prepares the animation to be started again the next time it's advanced
     fn reset(&mut self) {
         self.frame_num = 0;
-        self.last_update = Instant::now()
+        self.last_update = None;
+        self.sprite.set_src_rect_pos([0.0, 0.0].into());
+        self.align_sprite_to_bounds();
+    }
+
+    /// Opts in to fitting game object boxes to the opaque pixels of the current frame with
+    /// [SpriteAnimation::fit_box], keeping the given anchor of the box in place as it's resized.
+    /// While set, the sprite is drawn with its opaque pixels over the fitted box, whether or not
+    /// the game object's box has been fit yet. The frame bounds should be loaded up front with
+    /// [SpriteAnimation::load_frame_bounds], otherwise they're loaded the first time the animation is rendered.
+    /// Scenes fit their entities' boxes every tick with [RegisteredEntity::fit_box_to_sprite](crate::gameobject::entity::registry::RegisteredEntity::fit_box_to_sprite)
+    pub fn set_auto_fit(&mut self, anchor: Option<Anchor>) {
+        self.auto_fit = anchor;
+        self.align_sprite_to_bounds();
+    }
+
+    /// The anchor that boxes are fit around, if fitting boxes to the sprite is enabled
+    pub fn auto_fit(&self) -> Option<Anchor> {
+        self.auto_fit
+    }
+
+    /// Reads the animation's sprite map from the registry's assets and caches the bounds of the
+    /// opaque pixels in each frame. If the sprite map can't be read, the whole frames are cached
+    /// instead so that the read isn't retried
+    pub fn load_frame_bounds(&mut self, texture_registry: &TextureRegistry) -> RenderResult<()> {
+        match texture_registry.load_image(&self.sprite.texture_path) {
+            Ok(sprite_map) => {
+                self.compute_frame_bounds(&sprite_map);
+                Ok(())
+            }
+            Err(e) => {
+                self.use_whole_frame_bounds();
+                Err(e)
+            }
+        }
+    }
+
+    /// Caches the whole of each frame as its bounds
+    fn use_whole_frame_bounds(&mut self) {
+        let Size { w, h } = self.sprite.sprite_size;
+        self.frame_bounds = vec![Some([0.0, 0.0, w, h]); usize::max(self.frames_in_sprite_map, 1)];
+        self.align_sprite_to_bounds();
+    }
+
+    /// Caches the bounds of the opaque pixels in each frame of the given sprite map
+    pub fn compute_frame_bounds(&mut self, sprite_map: &RgbaImage) {
+        let Size { w, h } = self.sprite.sprite_size;
+        self.frame_bounds = (0..usize::max(self.frames_in_sprite_map, 1))
+            .map(|frame| {
+                let Point { x, y } = self.frame_src_pos(frame);
+                let src = [x as u32, y as u32, w as u32, h as u32];
+                bounds::opaque_bounds(sprite_map, src)
+                    .map(|[x, y, w, h]| [x as f64, y as f64, w as f64, h as f64])
+            })
+            .collect();
+        self.align_sprite_to_bounds();
+    }
+
+    /// The bounds `[x, y, w, h]` of the opaque pixels in the current frame, relative to the frame.
+    /// `None` if the frame is fully transparent or the bounds haven't been loaded
+    pub fn current_frame_bounds(&self) -> Option<[f64; 4]> {
+        self.frame_bounds.get(self.frame_num).copied().flatten()
+    }
+
+    /// Resizes the box to the opaque pixels of the current frame, keeping the box's
+    /// [auto fit](SpriteAnimation::set_auto_fit) anchor in place.
+    /// Does nothing unless auto fit is enabled and the current frame has bounds
+    pub fn fit_box(&self, physbox: &mut PhysBox) -> PhysicsResult<()> {
+        if let Some(fitted) = self.fitted_box(physbox)? {
+            *physbox = fitted;
+        }
+        Ok(())
+    }
+
+    /// The box resized to the opaque pixels of the current frame around the auto fit anchor,
+    /// or `None` if auto fit is disabled or the current frame has no bounds
+    fn fitted_box(&self, physbox: &PhysBox) -> PhysicsResult<Option<PhysBox>> {
+        match (self.auto_fit, self.current_frame_bounds()) {
+            (Some(anchor), Some([_x, _y, w, h])) => anchor.resize(physbox, [w, h]).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Offsets the sprite so that the current frame's opaque pixels are drawn at a fitted box's position.
+    /// The sprite's own position is kept, so disabling auto fit puts the sprite back where it was
+    fn align_sprite_to_bounds(&mut self) {
+        self.sprite.fit_offset = match (self.auto_fit, self.current_frame_bounds()) {
+            (Some(_anchor), Some([x, y, _w, _h])) => [x, y],
+            _ => [0.0, 0.0],
+        };
+    }
+
+    /// The number of frames in the animation. Static animations have 0 frames
+    pub fn frame_count(&self) -> usize {
+        self.frames_in_sprite_map
+    }
+
+    /// The frame the animation is currently showing
+    pub fn current_frame(&self) -> usize {
+        self.frame_num
+    }
+
+    /// The position in the sprite map of the given frame
+    fn frame_src_pos(&self, frame: usize) -> Point {
+        match self.animation_direction {
+            Axis::X => [frame as f64 * self.sprite.sprite_size.w, 0.0].into(),
+            Axis::Y => [0.0, frame as f64 * self.sprite.sprite_size.h].into(),
+        }
+    }
+
+    /// Moves the animation forward to the frame that should be showing at `now`.
+    /// If the animation hasn't started yet, it starts at `now`
+    pub(crate) fn advance_to(&mut self, now: Instant) {
+        let last_update = match self.last_update {
+            Some(last_update) => last_update,
+            None => {
+                self.last_update = Some(now);
+                return;
+            }
+        };
+
+        let num_new_frames = (now.saturating_duration_since(last_update).as_millis()
+            / self.milliseconds_per_frame as u128) as usize;
+        if num_new_frames > 0 && self.frames_in_sprite_map > 0 {
+            self.last_update = Some(now);
+            self.show_frame((self.frame_num + num_new_frames) % self.frames_in_sprite_map);
+        }
+    }
+
+    /// The time (in seconds) it takes to play every frame once. Static animations take no time
+    fn cycle_duration(&self) -> f64 {
+        self.frames_in_sprite_map as f64 * self.milliseconds_per_frame / 1000.0
+    }
+
+    /// Shows the frame that's the `phase` fraction (between 0 and 1) of the way through the animation
+    fn show_phase(&mut self, phase: f64) {
+        if self.frames_in_sprite_map > 0 {
+            let frame = (phase * self.frames_in_sprite_map as f64) as usize;
+            self.show_frame(usize::min(frame, self.frames_in_sprite_map - 1));
+        }
+    }
+
+    fn show_frame(&mut self, frame: usize) {
+        self.frame_num = frame;
+        self.sprite
+            .set_src_rect_pos(self.frame_src_pos(self.frame_num));
+        self.align_sprite_to_bounds();
+    }
+
+    /// Tiles the given frame of the animation across the viewed game object's box without
+    /// advancing the animation
+    pub(crate) fn render_frame_tiled<V: HasBox>(
+        &self,
+        viewed: &V,
+        frame: usize,
+        camera: &Camera,
+        ctx: graphics::Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut opengl_graphics::GlGraphics,
+    ) {
+        if let Some((transform, rect)) = camera.box_renderables(&viewed.get_render_box(), ctx) {
+            self.sprite.render_tiled_from(
+                self.frame_src_pos(frame),
+                rect,
+                camera.points_per_pixel(),
+                graphics::Context { transform, ..ctx },
+                texture_registry,
+                gl,
+            );
+        }
     }
 
     fn render<V: HasBox>(
         &mut self,
         viewed: &V,
-        args: &RenderArgs,
+        _args: &RenderArgs,
         camera: &Camera,
         ctx: graphics::Context,
         texture_registry: &TextureRegistry,
         gl: &mut opengl_graphics::GlGraphics,
     ) -> RenderResult<()> {
         // args.ext_dt is a liar, so we calculate our own dt
-        let now = Instant::now();
+        self.advance_to(camera.time_source().now());
 
-        let num_new_frames =
-            ((now - self.last_update).as_millis() / self.milliseconds_per_frame as u128) as usize;
-        if num_new_frames > 0 && self.frames_in_sprite_map > 0 {
-            self.last_update = now;
-            self.frame_num = (self.frame_num + num_new_frames) % self.frames_in_sprite_map;
-            let new_pos = match self.animation_direction {
-                Axis::X => [self.frame_num as f64 * self.sprite.sprite_size.w, 0.0].into(),
-                Axis::Y => [0.0, self.frame_num as f64 * self.sprite.sprite_size.h].into(),
-            };
-            self.sprite.set_src_rect_pos(new_pos)
+        self.render_with_alpha(viewed, camera, ctx, texture_registry, gl, 1.0)
+    }
+
+    /// Renders the current frame with its opacity multiplied by `alpha`.
+    /// With auto fit enabled the frame is drawn over the fitted box, loading the frame bounds if needed.
+    /// The frame bounds failing to load doesn't fail the render, the whole frame is fit instead
+    fn render_with_alpha<V: HasBox>(
+        &mut self,
+        viewed: &V,
+        camera: &Camera,
+        ctx: graphics::Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut opengl_graphics::GlGraphics,
+        alpha: f32,
+    ) -> RenderResult<()> {
+        if self.auto_fit.is_some() && self.frame_bounds.is_empty() {
+            if let Err(e) = self.load_frame_bounds(texture_registry) {
+                println!(
+                    "Error loading frame bounds for {:?}, fitting to whole frames: {:}",
+                    self.sprite.texture_path, e
+                );
+            }
         }
 
-        self.sprite
-            .render(viewed, args, camera, ctx, texture_registry, gl)
+        match self.fitted_box(&viewed.get_render_box()).ok().flatten() {
+            Some(fitted) => {
+                self.sprite
+                    .render_with_alpha(&fitted, camera, ctx, texture_registry, gl, alpha)
+            }
+            None => self
+                .sprite
+                .render_with_alpha(viewed, camera, ctx, texture_registry, gl, alpha),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// One of the two animations of a [BlendAnimation]
+pub enum BlendLayer {
+    /// The animation shown at a parameter of 0
+    From,
+    /// The animation shown at a parameter of 1
+    To,
+}
+
+#[derive(Derivative, Serialize, Deserialize)]
+#[derivative(Debug, Clone(bound = ""))]
+#[serde(bound = "")]
+/// A view type that blends between two [SpriteAnimation]s, like a 1-dimensional blend tree.
+///
+/// The blend is controlled by a parameter between 0 and 1 (i.e. an entity's speed going from walking to running)
+/// that the game can update every frame. In between, `from` is drawn fully opaque and `to` is faded in over it
+/// with the parameter as its opacity, so the blend cross-dissolves without the scene showing through.
+/// At 0 only `from` is drawn, and at 1 only `to` is drawn.
+///
+/// Both animations are driven by one shared phase through their cycles rather than their own frame rates,
+/// so they stay in step even with different frame counts, i.e. halfway through the walk cycle is always
+/// drawn with halfway through the run cycle. The phase advances on the camera's clock, taking as long
+/// per cycle as the two animations' cycle lengths blended by the parameter
+pub struct BlendAnimation<V> {
+    from: SpriteAnimation,
+    to: SpriteAnimation,
+    parameter: f64,
+    /// How far through their cycles both animations are, from 0 to 1
+    #[serde(default)]
+    phase: f64,
+    /// The timestamp the phase was last advanced at, or `None` if the blend starts the next time it's advanced
+    #[serde(skip)]
+    last_update: Option<Instant>,
+    #[derivative(Debug = "ignore")]
+    phantom: PhantomData<V>,
+}
+
+impl<V> BlendAnimation<V> {
+    /// Creates a new blend between two animations, starting with only `from` showing
+    pub fn new(from: SpriteAnimation, to: SpriteAnimation) -> Self {
+        let mut blend = Self {
+            from,
+            to,
+            parameter: 0.0,
+            phase: 0.0,
+            last_update: None,
+            phantom: PhantomData::default(),
+        };
+        blend.reset();
+        blend
+    }
+
+    /// Sets the blend parameter, clamped between 0 (only `from`) and 1 (only `to`)
+    pub fn set_parameter(&mut self, parameter: f64) {
+        self.parameter = parameter.clamp(0.0, 1.0);
+    }
+
+    /// The current blend parameter
+    pub fn parameter(&self) -> f64 {
+        self.parameter
+    }
+
+    /// The animations drawn for the current parameter, in the order they're drawn,
+    /// with the opacity each is drawn with
+    pub fn draw_passes(&self) -> Vec<(BlendLayer, f32)> {
+        if self.parameter <= 0.0 {
+            vec![(BlendLayer::From, 1.0)]
+        } else if self.parameter >= 1.0 {
+            vec![(BlendLayer::To, 1.0)]
+        } else {
+            vec![
+                (BlendLayer::From, 1.0),
+                (BlendLayer::To, self.parameter as f32),
+            ]
+        }
+    }
+
+    fn layer_mut(&mut self, layer: BlendLayer) -> &mut SpriteAnimation {
+        match layer {
+            BlendLayer::From => &mut self.from,
+            BlendLayer::To => &mut self.to,
+        }
+    }
+
+    /// How far through their cycles both animations are, from 0 to 1
+    pub fn phase(&self) -> f64 {
+        self.phase
+    }
+
+    /// Restarts both animations at the start of their cycles
+    pub fn reset(&mut self) {
+        self.from.reset();
+        self.to.reset();
+        self.phase = 0.0;
+        self.last_update = None;
+    }
+
+    /// Moves the shared phase forward to `now` and shows the matching frame of both animations.
+    /// If the blend hasn't started yet, it starts at `now`
+    fn advance_to(&mut self, now: Instant) {
+        let last_update = match self.last_update {
+            Some(last_update) => last_update,
+            None => {
+                self.last_update = Some(now);
+                return;
+            }
+        };
+        self.last_update = Some(now);
+
+        let from_duration = self.from.cycle_duration();
+        let duration = from_duration + (self.to.cycle_duration() - from_duration) * self.parameter;
+        if duration > 0.0 {
+            let dt = now.saturating_duration_since(last_update).as_secs_f64();
+            self.phase = (self.phase + dt / duration).fract();
+        }
+
+        self.from.show_phase(self.phase);
+        self.to.show_phase(self.phase);
+    }
+}
+
+impl<V: HasBox> View for BlendAnimation<V> {
+    type Viewed = V;
+
+    fn render(
+        &mut self,
+        viewed: &Self::Viewed,
+        _args: &RenderArgs,
+        camera: &Camera,
+        ctx: graphics::Context,
+        texture_registry: &TextureRegistry,
+        gl: &mut opengl_graphics::GlGraphics,
+    ) -> RenderResult<()> {
+        self.advance_to(camera.time_source().now());
+
+        for (layer, alpha) in self.draw_passes() {
+            self.layer_mut(layer).render_with_alpha(
+                viewed,
+                camera,
+                ctx,
+                texture_registry,
+                gl,
+                alpha,
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -210,11 +702,33 @@ impl<S: AnimationStates> AnimationStateMachine<S> {
         })
     }
 
+    /// The path of the sprite map for the current state's animation
+    pub fn texture_path(&self) -> Option<&Path> {
+        self.animations
+            .get(&self.current_state)
+            .map(SpriteAnimation::texture_path)
+    }
+
+    /// The area (in world coordinates) the current state's animation covers when drawn
+    /// for a game object with the given box
+    pub fn draw_bounds(&self, viewed_box: &PhysBox) -> Option<PhysBox> {
+        self.animations
+            .get(&self.current_state)
+            .and_then(|animation| animation.draw_bounds(viewed_box))
+    }
+
     /// Sets the SpriteAnimation for a given state
     pub fn set_state_animation(&mut self, state: S, animation: SpriteAnimation) {
         self.animations.insert(state, animation);
     }
 
+    /// Resizes the box to the current animation's sprite, see [SpriteAnimation::fit_box]
+    pub fn fit_box(&self, physbox: &mut PhysBox) -> PhysicsResult<()> {
+        self.animations
+            .get(&self.current_state)
+            .map_or(Ok(()), |animation| animation.fit_box(physbox))
+    }
+
     /// Sets the current state to new_state.
     /// Fails if there is no animation for new_state
     pub fn set_current_state(&mut self, new_state: S) -> Result<(), AnimationError> {
@@ -305,3 +819,236 @@ impl<S: AnimationStates> View for AnimationStateMachine<S> {
         animation.render(viewed, args, camera, ctx, texture_registry, gl)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use graphics::Context;
+    use image::Rgba;
+
+    use crate::{
+        gameobject::entity::Entity,
+        rendering::time::{MockClock, TimeSource},
+        types::physbox::HasBoxMut,
+    };
+
+    /// Creates an animation of 16x16 frames along the x axis without needing a loaded texture
+    pub(crate) fn create_test_animation(
+        milliseconds_per_frame: f64,
+        frames: usize,
+    ) -> SpriteAnimation {
+        SpriteAnimation {
+            sprite: SpriteView::new([0.0, 0.0].into(), [16.0, 16.0].into(), "test.png".into())
+                .unwrap(),
+            frames_in_sprite_map: frames,
+            frame_num: 0,
+            milliseconds_per_frame,
+            animation_direction: Axis::X,
+            last_update: None,
+            auto_fit: None,
+            frame_bounds: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn sprite_larger_than_clip_renders_scissored_to_the_clip() {
+        let mut sprite =
+            SpriteView::new([0.0, 0.0].into(), [128.0, 128.0].into(), "test.png".into()).unwrap();
+        assert_eq!(
+            sprite.clipped_context(Context::new()).draw_state.scissor,
+            None
+        );
+
+        sprite.set_clip(Some(ClipRect::new([10.0, 20.0, 30.0, 40.0])));
+        let ctx = sprite.clipped_context(Context::new());
+        assert_eq!(ctx.draw_state.scissor, Some([10, 20, 30, 40]));
+
+        // Nests with a clip the sprite is already rendered within
+        let outer = ClipRect::new([0.0, 0.0, 20.0, 50.0]).apply(Context::new());
+        let ctx = sprite.clipped_context(outer);
+        assert_eq!(ctx.draw_state.scissor, Some([10, 20, 10, 30]));
+    }
+
+    #[test]
+    fn blend_parameter_extremes_show_one_animation() {
+        let mut blend: BlendAnimation<PhysBox> = BlendAnimation::new(
+            create_test_animation(100.0, 4),
+            create_test_animation(100.0, 4),
+        );
+
+        assert_eq!(blend.draw_passes(), vec![(BlendLayer::From, 1.0)]);
+
+        blend.set_parameter(1.0);
+        assert_eq!(blend.draw_passes(), vec![(BlendLayer::To, 1.0)]);
+
+        blend.set_parameter(5.0);
+        assert_eq!(blend.parameter(), 1.0);
+        blend.set_parameter(-5.0);
+        assert_eq!(blend.parameter(), 0.0);
+    }
+
+    #[test]
+    fn blend_mid_parameter_blends_both_in_sync() {
+        let mut blend: BlendAnimation<PhysBox> = BlendAnimation::new(
+            create_test_animation(100.0, 4),
+            create_test_animation(100.0, 4),
+        );
+        blend.set_parameter(0.5);
+
+        assert_eq!(
+            blend.draw_passes(),
+            vec![(BlendLayer::From, 1.0), (BlendLayer::To, 0.5)]
+        );
+        // Alpha compositing the passes over the scene where both sprites are opaque
+        // leaves none of the scene showing through
+        let scene_showing = blend
+            .draw_passes()
+            .iter()
+            .fold(1.0, |showing, (_layer, alpha)| showing * (1.0 - alpha));
+        assert_eq!(scene_showing, 0.0);
+
+        let start = MockClock::new().now();
+        blend.advance_to(start);
+        assert_eq!(blend.last_update, Some(start));
+
+        blend.advance_to(start + Duration::from_millis(250));
+        assert_eq!(blend.from.frame_num, 2);
+        assert_eq!(blend.to.frame_num, 2);
+        assert_eq!(
+            blend.from.sprite.image.source_rectangle,
+            Some([32.0, 0.0, 16.0, 16.0])
+        );
+        assert_eq!(
+            blend.to.sprite.image.source_rectangle,
+            blend.from.sprite.image.source_rectangle
+        );
+
+        // Animations with the same cycle length keep the same timing whatever the parameter
+        blend.set_parameter(0.9);
+        blend.advance_to(start + Duration::from_millis(350));
+        assert_eq!(blend.from.frame_num, 3);
+        assert_eq!(blend.to.frame_num, 3);
+    }
+
+    #[test]
+    fn mock_clock_drives_frames_deterministically() {
+        let clock = MockClock::new();
+        let mut animation = create_test_animation(100.0, 4);
+        animation.advance_to(clock.now());
+
+        clock.advance(Duration::from_millis(99));
+        animation.advance_to(clock.now());
+        assert_eq!(animation.current_frame(), 0);
+
+        clock.advance(Duration::from_millis(1));
+        animation.advance_to(clock.now());
+        assert_eq!(animation.current_frame(), 1);
+        assert_eq!(
+            animation.sprite.image.source_rectangle,
+            Some([16.0, 0.0, 16.0, 16.0])
+        );
+
+        clock.advance(Duration::from_millis(250));
+        animation.advance_to(clock.now());
+        assert_eq!(animation.current_frame(), 3);
+
+        // Wraps back around to the start of the sprite map
+        clock.advance(Duration::from_millis(100));
+        animation.advance_to(clock.now());
+        assert_eq!(animation.current_frame(), 0);
+    }
+
+    #[test]
+    fn blend_animations_stay_in_phase() {
+        let clock = MockClock::new();
+        // A 0.4s walk cycle and a 0.2s run cycle with twice as many frames
+        let mut blend: BlendAnimation<PhysBox> = BlendAnimation::new(
+            create_test_animation(100.0, 4),
+            create_test_animation(25.0, 8),
+        );
+        blend.advance_to(clock.now());
+
+        // Only walking, so the phase moves at the walk cycle's speed
+        clock.advance(Duration::from_millis(200));
+        blend.advance_to(clock.now());
+        assert_eq!(blend.phase(), 0.5);
+        assert_eq!(blend.from.current_frame(), 2);
+        assert_eq!(blend.to.current_frame(), 4);
+
+        // Only running, so the phase moves at the run cycle's speed
+        blend.set_parameter(1.0);
+        clock.advance(Duration::from_millis(50));
+        blend.advance_to(clock.now());
+        assert_eq!(blend.phase(), 0.75);
+        assert_eq!(blend.from.current_frame(), 3);
+        assert_eq!(blend.to.current_frame(), 6);
+    }
+
+    #[test]
+    fn auto_fit_boxes_match_the_opaque_pixels_of_each_frame() {
+        let mut sprite_map = RgbaImage::new(32, 16);
+        for (frame_x, [x, y, w, h]) in [(0, [4, 2, 8, 14]), (16, [6, 8, 4, 8])] {
+            for px in x..x + w {
+                for py in y..y + h {
+                    sprite_map.put_pixel(frame_x + px, py, Rgba([255, 0, 0, 255]));
+                }
+            }
+        }
+
+        let clock = MockClock::new();
+        let mut animation = create_test_animation(100.0, 2);
+        animation.advance_to(clock.now());
+        animation.compute_frame_bounds(&sprite_map);
+
+        let mut entity = Entity::new().unwrap();
+        *entity.get_box_mut() = PhysBox::new([0.0, 0.0, 16.0, 16.0]).unwrap();
+
+        // Opt-in only
+        animation.fit_box(entity.get_box_mut()).unwrap();
+        assert_eq!(
+            *entity.get_box_mut(),
+            PhysBox::new([0.0, 0.0, 16.0, 16.0]).unwrap()
+        );
+
+        animation.set_auto_fit(Some(Anchor::BottomLeft));
+        *entity.get_box_mut() = PhysBox::new([4.0, 0.0, 8.0, 16.0]).unwrap();
+        animation.fit_box(entity.get_box_mut()).unwrap();
+        assert_eq!(
+            *entity.get_box_mut(),
+            PhysBox::new([4.0, 2.0, 8.0, 14.0]).unwrap()
+        );
+        // The sprite is drawn offset so its opaque pixels line up with the box
+        assert_eq!(animation.sprite.fit_offset, [4.0, 2.0]);
+        assert_eq!(animation.sprite.pos, Point::from([0.0, 0.0]));
+
+        clock.advance(Duration::from_millis(100));
+        animation.advance_to(clock.now());
+        animation.fit_box(entity.get_box_mut()).unwrap();
+        // The bottom left corner stays in place
+        assert_eq!(
+            *entity.get_box_mut(),
+            PhysBox::new([4.0, 8.0, 4.0, 8.0]).unwrap()
+        );
+        assert_eq!(animation.sprite.fit_offset, [6.0, 8.0]);
+
+        // Turning auto fit off draws the sprite where it was before
+        animation.set_auto_fit(None);
+        assert_eq!(animation.sprite.fit_offset, [0.0, 0.0]);
+        assert_eq!(animation.sprite.pos, Point::from([0.0, 0.0]));
+    }
+
+    #[test]
+    fn unreadable_frame_bounds_fit_the_whole_frame() {
+        let mut animation = create_test_animation(100.0, 2);
+        animation.set_auto_fit(Some(Anchor::BottomLeft));
+        animation.use_whole_frame_bounds();
+        assert!(!animation.frame_bounds.is_empty());
+
+        let mut physbox = PhysBox::new([4.0, 0.0, 8.0, 8.0]).unwrap();
+        animation.fit_box(&mut physbox).unwrap();
+        assert_eq!(physbox, PhysBox::new([4.0, -8.0, 16.0, 16.0]).unwrap());
+        assert_eq!(animation.sprite.fit_offset, [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,77 @@
+use core::fmt::Debug;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A trait for clocks that animations read the current time from
+pub trait TimeSource: Debug + Send + Sync {
+    /// The current time
+    fn now(&self) -> Instant;
+}
+
+/// A time source that can be shared between many animations
+pub type SharedTimeSource = Arc<dyn TimeSource>;
+
+/// The time source used by default, which reads the real time
+pub fn system_clock() -> SharedTimeSource {
+    Arc::new(SystemClock)
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Reads the real time from [Instant::now]
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug)]
+/// A clock that only moves when it's told to, for driving animations deterministically
+pub struct MockClock {
+    now: Mutex<Instant>,
+}
+
+impl MockClock {
+    /// Creates a mock clock stopped at the current real time
+    pub fn new() -> Self {
+        Self {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Moves the clock forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += duration;
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for MockClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mock_clock_only_moves_when_advanced() {
+        let clock = MockClock::new();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_millis(16));
+        assert_eq!(clock.now(), start + Duration::from_millis(16));
+    }
+}
@@ -1,20 +1,35 @@
-use std::fmt::Debug;
+use core::hash::Hasher;
+use std::{collections::BTreeMap, fmt::Debug};
 
 use graphics::Context;
 use opengl_graphics::GlGraphics;
 use piston::RenderArgs;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::{
-    effect::PendingEffect,
+    effect::{
+        contact::{ContactEvent, ContactPolicy, ContactTracker},
+        PendingEffect,
+    },
     gameobject::{
-        entity::registry::{EntityRegistry, RegisteredDebugEntity, RegisteredEntity},
-        field::Field,
+        entity::{
+            registry::{EntityRegistry, RegisteredDebugEntity, RegisteredEntity},
+            RenderVisibility,
+        },
+        field::{Cell, Field},
+    },
+    rendering::{
+        batch::{batch_draws, DrawItem, RenderStrategy},
+        debug::DebugView,
+        registry::TextureRegistry,
+        Camera, View,
     },
-    rendering::{debug::DebugView, registry::TextureRegistry, Camera, View},
     types::{
+        material::MaterialCombineRules,
         physbox::{HasBox, HasBoxMut},
-        HasSolidity,
+        state_hash::{StateHash, StateHasher},
+        BoxEdge, HasSolidity, HasUuid,
     },
     ScarabResult,
 };
@@ -28,6 +43,16 @@ pub struct Scene<E, V> {
     #[serde(skip)]
     #[serde(default = "Vec::new")]
     pending_effects: Vec<PendingEffect<E>>,
+    #[serde(default)]
+    material_rules: MaterialCombineRules,
+    #[serde(default)]
+    contact_policy: ContactPolicy,
+    #[serde(skip)]
+    contacts: ContactTracker,
+    #[serde(default)]
+    render_strategy: RenderStrategy,
+    #[serde(default)]
+    y_sort: bool,
 }
 
 impl<E, V> Scene<E, V>
@@ -42,6 +67,11 @@ where
             field_view,
             entity_registry: EntityRegistry::default(),
             pending_effects: Vec::default(),
+            material_rules: MaterialCombineRules::default(),
+            contact_policy: ContactPolicy::default(),
+            contacts: ContactTracker::default(),
+            render_strategy: RenderStrategy::default(),
+            y_sort: false,
         }
     }
 
@@ -57,8 +87,10 @@ where
         self.field_view
             .render(&mut self.field, args, &camera, ctx, texture_registry, gl)?;
 
-        for registered_entity in &mut self.entity_registry {
-            registered_entity.render(args, camera, ctx, texture_registry, gl)?;
+        for i in self.draw_order() {
+            if let Some(registered_entity) = self.entity_registry.get_one_mut(i) {
+                registered_entity.render(args, camera, ctx, texture_registry, gl)?;
+            }
         }
         Ok(())
     }
@@ -88,54 +120,256 @@ where
             gl,
         )?;
 
-        for registered_entity in &mut self.entity_registry {
-            registered_entity.render_with_info(
-                debug_options,
-                args,
-                camera,
-                ctx,
-                texture_registry,
-                gl,
-            )?;
+        for i in self.draw_order() {
+            if let Some(registered_entity) = self.entity_registry.get_one_mut(i) {
+                registered_entity.render_with_info(
+                    debug_options,
+                    args,
+                    camera,
+                    ctx,
+                    texture_registry,
+                    gl,
+                )?;
+            }
         }
         Ok(())
     }
 
+    /// The registry indices of the visible entities in the order they're drawn in.
+    /// Entities are drawn by their z layer, and within a layer by their y position if the scene
+    /// [y-sorts](Scene::set_y_sort). See [RenderOrder](crate::gameobject::entity::RenderOrder).
+    /// Entities with the same sort key are drawn in the order they were registered
+    pub fn render_order(&self) -> Vec<usize> {
+        let mut order: Vec<(usize, (i32, f64))> = self
+            .entity_registry
+            .iter()
+            .map(|registered_entity| registered_entity.inner_entity())
+            .enumerate()
+            .filter(|(_i, entity)| entity.visibility() != RenderVisibility::Hidden)
+            .map(|(i, entity)| {
+                let (z_layer, y) = entity.sort_key();
+                (i, (z_layer, if self.y_sort { y } else { 0.0 }))
+            })
+            .collect();
+
+        // sort_by is stable, so ties keep their registration order
+        order.sort_by(|(_, (a_layer, a_y)), (_, (b_layer, b_y))| {
+            a_layer.cmp(b_layer).then(a_y.total_cmp(b_y))
+        });
+
+        order.into_iter().map(|(i, _key)| i).collect()
+    }
+
+    /// Sets whether entities within the same z layer are drawn by the bottom of their box, so that
+    /// entities lower on screen are drawn over those behind them. Off by default, drawing entities
+    /// within a layer in the order they were registered
+    pub fn set_y_sort(&mut self, y_sort: bool) {
+        self.y_sort = y_sort;
+    }
+
+    /// Sets how the scene orders its entities' draws
+    pub fn set_render_strategy(&mut self, render_strategy: RenderStrategy) {
+        self.render_strategy = render_strategy;
+    }
+
+    /// The registry indices of the visible entities in the order they're actually drawn in.
+    /// This is the [render order](Scene::render_order), grouped by texture within each z layer
+    /// when using [RenderStrategy::Batched]
+    pub fn draw_order(&self) -> Vec<usize> {
+        let order = self.render_order();
+        match self.render_strategy {
+            RenderStrategy::Immediate => order,
+            RenderStrategy::Batched => {
+                let items = self.draw_items(&order);
+                batch_draws(&items)
+                    .into_iter()
+                    .map(|item_index| order[item_index])
+                    .collect()
+            }
+        }
+    }
+
+    /// The batching info of the entities at the given registry indices
+    fn draw_items(&self, order: &[usize]) -> Vec<DrawItem> {
+        order
+            .iter()
+            .filter_map(|&i| self.entity_registry.get_one(i))
+            .map(|registered_entity| DrawItem {
+                layer: registered_entity.inner_entity().render_order().z_layer,
+                texture: registered_entity.batch_texture(),
+                bounds: registered_entity.draw_bounds(),
+            })
+            .collect()
+    }
+
     /// Registers a new entity to the scene
     pub fn register_entity(&mut self, to_register: E) -> ScarabResult<()> {
         self.entity_registry.register(to_register)
     }
 
+    /// Returns the id of the first entity, in registration order, that satisfies `pred`.
+    /// Stops checking entities as soon as one matches
+    pub fn find<F: Fn(&E) -> bool>(&self, pred: F) -> Option<Uuid> {
+        self.entity_registry.find(pred)
+    }
+
+    /// Iterates, in registration order, across the ids of all entities that satisfy `pred`
+    pub fn filter<'a, F: Fn(&E) -> bool + 'a>(
+        &'a self,
+        pred: F,
+    ) -> impl Iterator<Item = Uuid> + 'a {
+        self.entity_registry.filter(pred)
+    }
+
+    /// Grows the scene's field from the given edge (see [Field::expand]).
+    /// Existing cells don't move, so entities and cameras don't need to be adjusted
+    pub fn expand_field(
+        &mut self,
+        edge: BoxEdge,
+        count: usize,
+        fill_cell: Cell,
+    ) -> ScarabResult<()> {
+        self.field.expand(edge, count, fill_cell)?;
+        Ok(())
+    }
+
     /// Gets a reference to the scene's [Field]
     pub fn get_field(&self) -> &Field {
         &self.field
     }
 
+    /// Sets the rules for combining the materials of colliding entities and cells
+    pub fn set_material_rules(&mut self, material_rules: MaterialCombineRules) {
+        self.material_rules = material_rules;
+    }
+
     /// Runs the physics update for all of the scene's entities
     pub fn tick_entities(&mut self, dt: f64) -> ScarabResult<()> {
         let mut args = GameTickArgs {
             field: &self.field,
             pending_effects: &mut self.pending_effects,
+            material_rules: &self.material_rules,
             dt,
         };
         for (i, registered_entity) in self.entity_registry.iter_mut().enumerate() {
+            registered_entity.fit_box_to_sprite()?;
+
+            let entity = registered_entity.inner_entity_mut();
+            if !entity.begin_tick() {
+                continue;
+            }
+
+            // Entities that update less often move as far per update as they would've
+            // over all the ticks they skipped
+            args.dt = dt * entity.update_rate().ticks_per_update() as f64;
             registered_entity.game_tick(i, &mut args)?;
         }
 
-        self.handle_entity_collisions()?;
+        self.handle_entity_collisions(dt)?;
+
+        self.update_contacts(dt);
 
         self.process_pending_effects()?;
 
         Ok(())
     }
 
+    /// Whether the entity `from` can see the entity `to`, looking from the center of one's box to the other's.
+    /// The view is blocked by completely solid field cells, and by the boxes of any other entities
+    /// that `occluder_filter` returns true for. The two entities themselves never block the view.
+    /// Returns false if either entity isn't registered
+    pub fn line_of_sight<F: Fn(&E) -> bool>(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        occluder_filter: F,
+    ) -> bool {
+        let find_center = |uuid: Uuid| {
+            self.entity_registry
+                .iter()
+                .find(|registered_entity| registered_entity.uuid() == uuid)
+                .map(|registered_entity| registered_entity.get_box().center())
+        };
+        let (from_center, to_center) = match (find_center(from), find_center(to)) {
+            (Some(from_center), Some(to_center)) => (from_center, to_center),
+            _ => return false,
+        };
+
+        if self.field.is_segment_blocked(from_center, to_center) {
+            return false;
+        }
+
+        !self.entity_registry.iter().any(|registered_entity| {
+            let uuid = registered_entity.uuid();
+            uuid != from
+                && uuid != to
+                && occluder_filter(registered_entity)
+                && registered_entity
+                    .get_box()
+                    .intersects_segment(from_center, to_center)
+        })
+    }
+
+    /// A fingerprint of the scene's simulation state, for detecting when two runs of the
+    /// same game (i.e. a client and server, or a game and its replay) have diverged.
+    /// Identical states always hash the same, regardless of entity uuids or render-only state
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::default();
+        self.field.hash_state(&mut hasher);
+
+        hasher.write_usize(self.entity_registry.len());
+        for registered_entity in self.entity_registry.iter() {
+            registered_entity.hash_state(&mut hasher);
+        }
+
+        let indices: BTreeMap<Uuid, usize> = self
+            .entity_registry
+            .iter()
+            .enumerate()
+            .map(|(i, registered_entity)| (registered_entity.uuid(), i))
+            .collect();
+        self.contacts
+            .hash_state(&mut hasher, |uuid| indices.get(&uuid).copied());
+
+        hasher.finish()
+    }
+
+    /// Optionally returns a reference to the scene's player
+    pub fn player(&self) -> Option<&E::Player> {
+        self.entity_registry.player()
+    }
+
     // TODO! Find a way to pin the return type of this to a specific type within the registry
     /// Optionally returns a mutable reference to the scene's player
     pub fn player_mut(&mut self) -> Option<&mut E::Player> {
         self.entity_registry.player_mut()
     }
 
-    fn handle_entity_collisions(&mut self) -> ScarabResult<()> {
+    /// Sets how often contact events are sent for entities that stay touching
+    pub fn set_contact_policy(&mut self, contact_policy: ContactPolicy) {
+        self.contact_policy = contact_policy;
+    }
+
+    /// The contact events between entities from the latest game tick,
+    /// ordered by when the entities were registered
+    pub fn contact_events(&self) -> &[ContactEvent] {
+        self.contacts.events()
+    }
+
+    fn update_contacts(&mut self, dt: f64) {
+        let mut touching = Vec::new();
+        for (this_index, this_one) in self.entity_registry.iter().enumerate() {
+            for other_one in self.entity_registry.iter().skip(this_index + 1) {
+                if this_one.get_box().is_touching(other_one.get_box()) {
+                    touching.push((this_one.uuid(), other_one.uuid()));
+                }
+            }
+        }
+
+        self.contacts.update(touching, dt, &self.contact_policy);
+    }
+
+    fn handle_entity_collisions(&mut self, dt: f64) -> ScarabResult<()> {
         // This is kinda gross, but I don't really know how else to do it
         // we'll see later how necessary it is to change
         for this_index in 0..self.entity_registry.len() {
@@ -145,6 +379,8 @@ where
                 }
 
                 let this_one_box = *this_one.get_box();
+                let this_one_material = *this_one.inner_entity().material();
+                let mut this_one_contacts = Vec::new();
 
                 for other_index in 0..this_index {
                     if this_index == other_index {
@@ -152,12 +388,33 @@ where
                     }
                     if let Some(other_one) = self.entity_registry.get_one_mut(other_index) {
                         if other_one.get_solidity().has_solidity() {
-                            other_one
+                            if let Some(edge) = other_one
                                 .get_box_mut()
-                                .shift_to_nonoverlapping(&this_one_box);
+                                .shift_to_nonoverlapping(&this_one_box)
+                            {
+                                let other_one_material = *other_one.inner_entity().material();
+                                other_one.inner_entity_mut().apply_contact(
+                                    edge,
+                                    &this_one_material,
+                                    &self.material_rules,
+                                    dt,
+                                );
+                                this_one_contacts.push((edge.opposite(), other_one_material));
+                            }
                         }
                     }
                 }
+
+                if let Some(this_one) = self.entity_registry.get_one_mut(this_index) {
+                    for (edge, material) in this_one_contacts {
+                        this_one.inner_entity_mut().apply_contact(
+                            edge,
+                            &material,
+                            &self.material_rules,
+                            dt,
+                        );
+                    }
+                }
             }
         }
         Ok(())
@@ -211,6 +468,499 @@ pub struct GameTickArgs<'a, E> {
     pub field: &'a Field,
     /// The current attacks waiting to be processed in the game loop. Add to this to attack another entity
     pub pending_effects: &'a mut Vec<PendingEffect<E>>,
+    /// The rules for combining the materials of colliding game objects
+    pub material_rules: &'a MaterialCombineRules,
     /// The change in time for this update
     pub dt: f64,
 }
+
+#[cfg(test)]
+mod test {
+    use shapes::Point;
+
+    use super::*;
+    use crate::{
+        effect::contact::ContactPhase,
+        gameobject::{
+            entity::{registry::test::TestEntity, RenderOrder, UpdateRate},
+            field::{CellColorView, FieldColorView},
+        },
+        rendering::batch::count_texture_binds,
+        types::{
+            material::{MaterialCombine, PhysicsMaterial},
+            physbox::PhysBox,
+            HasHealth, Velocity, NO_SOLIDITY, SOLID,
+        },
+    };
+
+    fn create_test_scene() -> Scene<TestEntity, FieldColorView> {
+        let field = Field::new(vec![Cell::new(
+            NO_SOLIDITY,
+            PhysBox::new([-100.0, -100.0, 200.0, 200.0]).unwrap(),
+        )])
+        .unwrap();
+        let color_view = CellColorView { color: [1.0; 4] };
+        let field_view = FieldColorView {
+            solid_view: color_view.clone(),
+            air_view: color_view.clone(),
+            default_view: color_view,
+        };
+
+        Scene::new(field, field_view)
+    }
+
+    /// Registers an entity moving right into a stationary one.
+    /// The moving entity doesn't bounce and the stationary one is perfectly bouncy
+    fn register_colliding_entities(scene: &mut Scene<TestEntity, FieldColorView>) {
+        let mut moving = TestEntity::at([0.0, 0.0, 4.0, 4.0]);
+        moving.0.set_max_velocity(10.0).unwrap();
+        moving.0.set_velocity([5.0, 0.0].into());
+        moving.0.set_material(PhysicsMaterial::new(0.0, 0.0));
+
+        let mut bouncy = TestEntity::at([3.0, 0.0, 4.0, 4.0]);
+        bouncy.0.set_material(PhysicsMaterial::new(0.0, 1.0));
+
+        scene.register_entity(moving).unwrap();
+        scene.register_entity(bouncy).unwrap();
+    }
+
+    #[test]
+    fn entity_collision_bounces_by_max_restitution() {
+        let mut scene = create_test_scene();
+        scene.set_material_rules(MaterialCombineRules {
+            keep_velocity_into_surfaces: false,
+            ..Default::default()
+        });
+        register_colliding_entities(&mut scene);
+
+        scene.handle_entity_collisions(1.0).unwrap();
+
+        let moving = scene.entity_registry.get_one(0).unwrap();
+        let bouncy = scene.entity_registry.get_one(1).unwrap();
+        assert!(!moving.get_box().has_overlap(bouncy.get_box()));
+        assert_eq!(moving.0.get_velocity(), Velocity { x: -5.0, y: 0.0 });
+        assert_eq!(bouncy.0.get_velocity(), Velocity { x: 0.0, y: 0.0 });
+    }
+
+    #[test]
+    fn entity_collision_uses_configured_restitution_rule() {
+        let mut scene = create_test_scene();
+        scene.set_material_rules(MaterialCombineRules {
+            restitution: MaterialCombine::Average,
+            keep_velocity_into_surfaces: false,
+            ..Default::default()
+        });
+        register_colliding_entities(&mut scene);
+
+        scene.handle_entity_collisions(1.0).unwrap();
+
+        let moving = scene.entity_registry.get_one(0).unwrap();
+        assert_eq!(moving.0.get_velocity(), Velocity { x: -2.5, y: 0.0 });
+
+        let mut scene = create_test_scene();
+        scene.set_material_rules(MaterialCombineRules {
+            restitution: MaterialCombine::Multiply,
+            keep_velocity_into_surfaces: false,
+            ..Default::default()
+        });
+        register_colliding_entities(&mut scene);
+
+        scene.handle_entity_collisions(1.0).unwrap();
+
+        // No bounce, so the entity stops against the other
+        let moving = scene.entity_registry.get_one(0).unwrap();
+        assert_eq!(moving.0.get_velocity(), Velocity { x: 0.0, y: 0.0 });
+    }
+
+    #[test]
+    fn expanding_field_keeps_entities_on_their_cells() {
+        let mut scene = create_test_scene();
+        scene
+            .register_entity(TestEntity::at([10.0, 20.0, 4.0, 4.0]))
+            .unwrap();
+        let before = scene
+            .field
+            .cell_at_pos([10.0, 20.0].into())
+            .unwrap()
+            .clone();
+
+        let fill = Cell::new(SOLID, PhysBox::new([0.0, 0.0, 5.0, 1.0]).unwrap());
+        scene.expand_field(BoxEdge::Left, 2, fill).unwrap();
+
+        let entity_pos = *scene.entity_registry.get_one(0).unwrap().get_box().pos();
+        assert_eq!(entity_pos, Point { x: 10.0, y: 20.0 });
+        assert_eq!(scene.field.cell_at_pos(entity_pos), Some(&before));
+
+        // The new cells are to the left of the old field
+        let new_cell = scene.field.cell_at_pos([-110.0, 0.0].into()).unwrap();
+        assert_eq!(new_cell.get_solidity(), &SOLID);
+    }
+
+    #[test]
+    fn half_rate_entity_updates_every_other_tick_and_renders_interpolated() {
+        let mut scene = create_test_scene();
+        let mut entity = TestEntity::at([0.0, 0.0, 4.0, 4.0]);
+        entity.0.set_max_velocity(10.0).unwrap();
+        entity.0.set_velocity([2.0, 0.0].into());
+        entity.0.set_update_rate(UpdateRate::every(2));
+        scene.register_entity(entity).unwrap();
+
+        let mut positions = Vec::new();
+        let mut render_positions = Vec::new();
+        for _ in 0..4 {
+            scene.tick_entities(1.0).unwrap();
+            let entity = scene.entity_registry.get_one(0).unwrap();
+            positions.push(entity.get_box().pos().x);
+            render_positions.push(entity.get_render_box().pos().x);
+        }
+
+        // Updates cover both ticks' worth of movement at once
+        assert_eq!(positions, vec![4.0, 4.0, 8.0, 8.0]);
+        // But the entity is still drawn moving smoothly every tick
+        assert_eq!(render_positions, vec![2.0, 4.0, 6.0, 8.0]);
+    }
+
+    #[test]
+    fn shifting_interpolated_entities_keeps_them_moving_smoothly() {
+        let mut scene = create_test_scene();
+        let mut entity = TestEntity::at([0.0, 0.0, 4.0, 4.0]);
+        entity.0.set_max_velocity(10.0).unwrap();
+        entity.0.set_velocity([2.0, 0.0].into());
+        entity.0.set_update_rate(UpdateRate::every(2));
+        scene.register_entity(entity).unwrap();
+
+        scene.tick_entities(1.0).unwrap();
+        scene
+            .entity_registry
+            .get_one_mut(0)
+            .unwrap()
+            .0
+            .shift([10.0, 0.0].into());
+
+        // Drawn at the same spot relative to the field, not sliding from its old position
+        let entity = scene.entity_registry.get_one(0).unwrap();
+        assert_eq!(entity.get_box().pos().x, 14.0);
+        assert_eq!(entity.get_render_box().pos().x, 12.0);
+    }
+
+    #[test]
+    fn identical_scenes_hash_the_same() {
+        let build_scene = || {
+            let mut scene = create_test_scene();
+            register_colliding_entities(&mut scene);
+            scene
+        };
+        let mut scene = build_scene();
+        let other = build_scene();
+        assert_eq!(scene.state_hash(), other.state_hash());
+
+        scene
+            .entity_registry
+            .get_one_mut(1)
+            .unwrap()
+            .get_box_mut()
+            .pos_mut()
+            .x += 1.0;
+        assert_ne!(scene.state_hash(), other.state_hash());
+    }
+
+    #[test]
+    fn contact_state_is_part_of_the_hash() {
+        let build_scene = || {
+            let mut scene = create_test_scene();
+            scene.set_contact_policy(ContactPolicy { stay_interval: 1.0 });
+            scene
+                .register_entity(TestEntity::at([0.0, 0.0, 4.0, 4.0]))
+                .unwrap();
+            scene
+                .register_entity(TestEntity::at([4.0, 0.0, 4.0, 4.0]))
+                .unwrap();
+            scene
+        };
+        let mut scene = build_scene();
+        let mut other = build_scene();
+        scene.tick_entities(0.125).unwrap();
+        other.tick_entities(0.125).unwrap();
+        assert_eq!(scene.state_hash(), other.state_hash());
+
+        // Same entity state, but one contact is further through its stay interval
+        let touching = |scene: &Scene<_, _>| {
+            let a = scene.entity_registry.get_one(0).unwrap().uuid();
+            let b = scene.entity_registry.get_one(1).unwrap().uuid();
+            vec![(a, b)]
+        };
+        let (scene_touching, other_touching) = (touching(&scene), touching(&other));
+        let policy = scene.contact_policy;
+        scene.contacts.update(scene_touching, 0.25, &policy);
+        other.contacts.update(other_touching, 0.5, &policy);
+        assert_ne!(scene.state_hash(), other.state_hash());
+
+        // Pending events differ even when the intervals match
+        let mut without_events = build_scene();
+        without_events.tick_entities(0.125).unwrap();
+        let touching = touching(&without_events);
+        without_events
+            .contacts
+            .update(touching.clone(), 0.0, &policy);
+        let mut with_events = build_scene();
+        with_events.tick_entities(0.125).unwrap();
+        with_events.contacts.update(Vec::new(), 0.0, &policy);
+        with_events.contacts.update(touching, 0.0, &policy);
+        assert_ne!(without_events.state_hash(), with_events.state_hash());
+    }
+
+    #[test]
+    fn sort_bias_draws_held_item_above_holder() {
+        let mut scene = create_test_scene();
+        scene.set_y_sort(true);
+        let holder = TestEntity::at([0.0, 10.0, 4.0, 4.0]);
+        // The item is held higher up than the holder's feet, so it'd normally be drawn first
+        let mut item = TestEntity::at([2.0, 8.0, 2.0, 2.0]);
+        item.0.set_render_order(RenderOrder {
+            z_layer: 0,
+            sort_bias: 5.0,
+        });
+        // A background entity on a lower layer stays under both, no matter its y
+        let mut background = TestEntity::at([0.0, 50.0, 4.0, 4.0]);
+        background.0.set_render_order(RenderOrder {
+            z_layer: -1,
+            sort_bias: 0.0,
+        });
+        let tied_with_holder = TestEntity::at([10.0, 10.0, 4.0, 4.0]);
+
+        scene.register_entity(item).unwrap();
+        scene.register_entity(holder).unwrap();
+        scene.register_entity(background).unwrap();
+        scene.register_entity(tied_with_holder).unwrap();
+
+        assert_eq!(scene.render_order(), vec![2, 1, 3, 0]);
+    }
+
+    #[test]
+    fn entities_draw_in_registration_order_unless_y_sorted() {
+        let mut scene = create_test_scene();
+        scene
+            .register_entity(TestEntity::at([0.0, 10.0, 4.0, 4.0]))
+            .unwrap();
+        scene
+            .register_entity(TestEntity::at([0.0, 0.0, 4.0, 4.0]))
+            .unwrap();
+        let mut background = TestEntity::at([0.0, 50.0, 4.0, 4.0]);
+        background.0.set_render_order(RenderOrder {
+            z_layer: -1,
+            sort_bias: 0.0,
+        });
+        scene.register_entity(background).unwrap();
+
+        // Layers still apply, but y doesn't
+        assert_eq!(scene.render_order(), vec![2, 0, 1]);
+
+        scene.set_y_sort(true);
+        assert_eq!(scene.render_order(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn hidden_entities_are_not_drawn() {
+        let mut scene = create_test_scene();
+        let mut hidden = TestEntity::at([0.0, 0.0, 4.0, 4.0]);
+        hidden.0.set_visibility(RenderVisibility::Hidden);
+        scene.register_entity(hidden).unwrap();
+        scene
+            .register_entity(TestEntity::at([10.0, 0.0, 4.0, 4.0]))
+            .unwrap();
+
+        assert_eq!(scene.render_order(), vec![1]);
+    }
+
+    #[test]
+    fn entities_between_block_line_of_sight_unless_filtered_out() {
+        let mut scene = create_test_scene();
+        let guard = TestEntity::at([-50.0, 0.0, 4.0, 4.0]);
+        let player = TestEntity::at([50.0, 0.0, 4.0, 4.0]);
+        let crate_box = TestEntity::at([0.0, -10.0, 4.0, 24.0]);
+        let ally = TestEntity::at([20.0, 0.0, 4.0, 4.0]);
+        let (guard_id, player_id, crate_id, ally_id) =
+            (guard.uuid(), player.uuid(), crate_box.uuid(), ally.uuid());
+        scene.register_entity(guard).unwrap();
+        scene.register_entity(player).unwrap();
+
+        // The ends of the line of sight are never occluders, even when the filter accepts them
+        assert!(scene.line_of_sight(guard_id, player_id, |_| true));
+
+        scene.register_entity(ally).unwrap();
+        assert!(!scene.line_of_sight(guard_id, player_id, |_| true));
+        // Allies can be seen through
+        let not_ally = |e: &TestEntity| e.uuid() != ally_id;
+        assert!(scene.line_of_sight(guard_id, player_id, not_ally));
+
+        scene.register_entity(crate_box).unwrap();
+        assert!(!scene.line_of_sight(guard_id, player_id, not_ally));
+        assert!(!scene.line_of_sight(player_id, guard_id, |e| e.uuid() == crate_id));
+        assert!(scene.line_of_sight(guard_id, player_id, |_| false));
+
+        assert!(!scene.line_of_sight(guard_id, Uuid::new_v4(), |_| false));
+    }
+
+    #[test]
+    fn solid_cells_block_line_of_sight() {
+        let field = Field::new(vec![
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap()),
+            Cell::new(SOLID, PhysBox::new([10.0, 0.0, 10.0, 10.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([20.0, 0.0, 10.0, 10.0]).unwrap()),
+            Cell::new(NO_SOLIDITY, PhysBox::new([0.0, 10.0, 30.0, 10.0]).unwrap()),
+        ])
+        .unwrap();
+        let mut scene = Scene::new(field, create_test_scene().field_view);
+        let left = TestEntity::at([2.0, 2.0, 2.0, 2.0]);
+        let right = TestEntity::at([24.0, 2.0, 2.0, 2.0]);
+        let below = TestEntity::at([24.0, 14.0, 2.0, 2.0]);
+        let (left_id, right_id, below_id) = (left.uuid(), right.uuid(), below.uuid());
+        scene.register_entity(left).unwrap();
+        scene.register_entity(right).unwrap();
+        scene.register_entity(below).unwrap();
+
+        assert!(!scene.line_of_sight(left_id, right_id, |_| true));
+        assert!(scene.line_of_sight(right_id, below_id, |_| true));
+    }
+
+    #[test]
+    fn resting_in_damage_zone_takes_damage_at_stay_interval() {
+        let mut scene = create_test_scene();
+        scene.set_contact_policy(ContactPolicy { stay_interval: 0.5 });
+        let mut zone = TestEntity::at([0.0, 0.0, 10.0, 10.0]);
+        zone.0.set_solidity(NO_SOLIDITY);
+        let zone_id = zone.uuid();
+        let player = TestEntity::at([2.0, 2.0, 2.0, 2.0]);
+        let player_id = player.uuid();
+        scene.register_entity(zone).unwrap();
+        scene.register_entity(player).unwrap();
+
+        let mut damage_ticks = Vec::new();
+        for tick in 0..16 {
+            scene.tick_entities(0.125).unwrap();
+            let events: Vec<ContactEvent> = scene.contact_events().to_vec();
+            for event in events {
+                if event.other(zone_id) == Some(player_id) && event.phase != ContactPhase::Exit {
+                    damage_ticks.push(tick);
+                    scene
+                        .entity_registry
+                        .get_one_mut(1)
+                        .unwrap()
+                        .get_health_mut()
+                        .raw_damage(1.0);
+                }
+            }
+        }
+
+        // Once on entering, then every half second (4 ticks) rather than every tick
+        assert_eq!(damage_ticks, vec![0, 4, 8, 12]);
+        let player = scene.entity_registry.get_one(1).unwrap();
+        assert_eq!(player.get_health().current(), 6.0);
+
+        scene
+            .entity_registry
+            .get_one_mut(1)
+            .unwrap()
+            .get_box_mut()
+            .pos_mut()
+            .x = 50.0;
+        scene.tick_entities(0.125).unwrap();
+        assert_eq!(
+            scene.contact_events(),
+            &[ContactEvent {
+                a: zone_id,
+                b: player_id,
+                phase: ContactPhase::Exit,
+            }]
+        );
+        scene.tick_entities(0.125).unwrap();
+        assert!(scene.contact_events().is_empty());
+    }
+
+    #[test]
+    fn batching_groups_textures_within_layers_only() {
+        let mut scene = create_test_scene();
+        let on_layer = |z_layer: i32, texture: &str, x: f64| {
+            let mut entity = TestEntity::at([x, 0.0, 4.0, 4.0]).with_texture(texture);
+            entity.0.set_render_order(RenderOrder {
+                z_layer,
+                sort_bias: 0.0,
+            });
+            entity
+        };
+        scene.register_entity(on_layer(1, "tree.png", 0.0)).unwrap();
+        scene
+            .register_entity(on_layer(0, "grass.png", 10.0))
+            .unwrap();
+        scene
+            .register_entity(on_layer(0, "rock.png", 20.0))
+            .unwrap();
+        scene
+            .register_entity(on_layer(1, "bush.png", 30.0))
+            .unwrap();
+        scene
+            .register_entity(on_layer(0, "grass.png", 40.0))
+            .unwrap();
+        scene
+            .register_entity(on_layer(1, "tree.png", 50.0))
+            .unwrap();
+
+        let immediate = scene.render_order();
+        assert_eq!(immediate, vec![1, 2, 4, 0, 3, 5]);
+
+        let batched = scene.draw_order();
+        assert_eq!(batched, vec![1, 4, 2, 0, 5, 3]);
+
+        let items = scene.draw_items(&[0, 1, 2, 3, 4, 5]);
+        assert_eq!(count_texture_binds(&items, &immediate), 6);
+        assert_eq!(count_texture_binds(&items, &batched), 4);
+
+        scene.set_render_strategy(RenderStrategy::Immediate);
+        assert_eq!(scene.draw_order(), immediate);
+    }
+
+    #[test]
+    fn batching_keeps_order_of_overlapping_sprites_with_disjoint_boxes() {
+        let mut scene = create_test_scene();
+
+        scene
+            .register_entity(TestEntity::at([0.0, 0.0, 4.0, 4.0]).with_texture("grass.png"))
+            .unwrap();
+        // The rock's sprite reaches 8 to the left of its box, over the second grass' box
+        scene
+            .register_entity(
+                TestEntity::at([20.0, 0.0, 4.0, 4.0])
+                    .with_sprite("rock.png", [-8.0, 0.0, 12.0, 4.0]),
+            )
+            .unwrap();
+        scene
+            .register_entity(TestEntity::at([10.0, 0.0, 4.0, 4.0]).with_texture("grass.png"))
+            .unwrap();
+
+        let boxes: Vec<PhysBox> = (1..3)
+            .map(|i| *scene.entity_registry.get_one(i).unwrap().get_box())
+            .collect();
+        assert!(!boxes[0].has_overlap(&boxes[1]));
+
+        assert_eq!(scene.draw_order(), scene.render_order());
+    }
+
+    #[test]
+    fn new_scenes_batch_draws_by_default() {
+        let mut scene = create_test_scene();
+        for (i, texture) in ["grass.png", "rock.png", "grass.png", "rock.png"]
+            .into_iter()
+            .enumerate()
+        {
+            let x = 10.0 * i as f64;
+            scene
+                .register_entity(TestEntity::at([x, 0.0, 4.0, 4.0]).with_texture(texture))
+                .unwrap();
+        }
+
+        let order = scene.draw_order();
+        assert_eq!(order, vec![0, 2, 1, 3]);
+        let items = scene.draw_items(&[0, 1, 2, 3]);
+        assert_eq!(count_texture_binds(&items, &order), 2);
+    }
+}
@@ -0,0 +1,158 @@
+use graphics::types::Scalar;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// The surface properties of a game object that are used when resolving its collisions
+pub struct PhysicsMaterial {
+    /// The fraction of the velocity along a contact's surface that is lost per second of touching it.
+    /// 0 is frictionless
+    pub friction: Scalar,
+    /// The fraction of the velocity into a contact's surface that is bounced back.
+    /// 0 doesn't bounce at all, 1 is a perfect bounce
+    pub restitution: Scalar,
+}
+
+impl PhysicsMaterial {
+    /// Creates a new material with the given friction and restitution
+    pub fn new(friction: Scalar, restitution: Scalar) -> Self {
+        Self {
+            friction,
+            restitution,
+        }
+    }
+}
+
+impl Default for PhysicsMaterial {
+    /// A frictionless material that doesn't bounce
+    fn default() -> Self {
+        Self::new(0.0, 0.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// A rule for combining a material property of two colliding game objects into one value.
+/// Every rule is symmetric, so the order of the two objects doesn't matter
+pub enum MaterialCombine {
+    /// The mean of the two values
+    Average,
+    /// The smaller of the two values
+    Min,
+    /// The larger of the two values
+    Max,
+    /// The product of the two values
+    Multiply,
+    /// The square root of the product of the two values
+    GeometricMean,
+}
+
+impl MaterialCombine {
+    /// Combines the property values of two colliding game objects
+    pub fn combine(&self, a: Scalar, b: Scalar) -> Scalar {
+        match self {
+            Self::Average => (a + b) / 2.0,
+            Self::Min => f64::min(a, b),
+            Self::Max => f64::max(a, b),
+            Self::Multiply => a * b,
+            Self::GeometricMean => f64::sqrt(a * b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The rules used to combine the materials of two colliding game objects
+pub struct MaterialCombineRules {
+    /// How the friction of the two materials is combined
+    pub friction: MaterialCombine,
+    /// How the restitution of the two materials is combined
+    pub restitution: MaterialCombine,
+    /// When set, game objects keep their velocity into the surfaces they touch instead of being
+    /// stopped or bounced back by the combined restitution. Their boxes are still kept from overlapping.
+    /// Set by default, so contacts only change velocities (besides friction) once this is cleared
+    #[serde(default = "keep_velocity_into_surfaces_default")]
+    pub keep_velocity_into_surfaces: bool,
+}
+
+fn keep_velocity_into_surfaces_default() -> bool {
+    true
+}
+
+impl MaterialCombineRules {
+    /// The combined (friction, restitution) for a collision between the two materials
+    pub fn combine(&self, a: &PhysicsMaterial, b: &PhysicsMaterial) -> (Scalar, Scalar) {
+        (
+            self.friction.combine(a.friction, b.friction),
+            self.restitution.combine(a.restitution, b.restitution),
+        )
+    }
+}
+
+impl Default for MaterialCombineRules {
+    /// The geometric mean for friction and the max for restitution, keeping velocity into surfaces
+    fn default() -> Self {
+        Self {
+            friction: MaterialCombine::GeometricMean,
+            restitution: MaterialCombine::Max,
+            keep_velocity_into_surfaces: keep_velocity_into_surfaces_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{BoxEdge, Velocity};
+
+    const ALL_RULES: [MaterialCombine; 5] = [
+        MaterialCombine::Average,
+        MaterialCombine::Min,
+        MaterialCombine::Max,
+        MaterialCombine::Multiply,
+        MaterialCombine::GeometricMean,
+    ];
+
+    #[test]
+    fn combine_is_symmetric() {
+        for rule in ALL_RULES {
+            assert_eq!(rule.combine(0.2, 0.8), rule.combine(0.8, 0.2));
+            assert_eq!(rule.combine(0.0, 1.0), rule.combine(1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn combine_rules_work() {
+        assert_eq!(MaterialCombine::Average.combine(0.0, 1.0), 0.5);
+        assert_eq!(MaterialCombine::Min.combine(0.0, 1.0), 0.0);
+        assert_eq!(MaterialCombine::Max.combine(0.0, 1.0), 1.0);
+        assert_eq!(MaterialCombine::Multiply.combine(0.5, 0.5), 0.25);
+        assert_eq!(MaterialCombine::GeometricMean.combine(0.25, 1.0), 0.5);
+    }
+
+    #[test]
+    fn default_rules_are_geometric_mean_friction_and_max_restitution() {
+        let a = PhysicsMaterial::new(0.25, 0.0);
+        let b = PhysicsMaterial::new(1.0, 1.0);
+
+        assert_eq!(MaterialCombineRules::default().combine(&a, &b), (0.5, 1.0));
+    }
+
+    #[test]
+    fn contact_is_continuous_in_restitution_and_independent_of_tick_rate() {
+        let velocity = Velocity { x: 4.0, y: 2.0 };
+
+        let no_bounce = velocity.after_contact(BoxEdge::Right, 0.0, 0.0, 1.0);
+        let small_bounce = velocity.after_contact(BoxEdge::Right, 0.0, 0.01, 1.0);
+        assert_eq!(no_bounce.x, 0.0);
+        assert!(small_bounce.x < 0.0 && small_bounce.x > -0.05);
+
+        let one_tick = velocity.after_contact(BoxEdge::Right, 0.75, 0.0, 1.0);
+        let half_tick = velocity.after_contact(BoxEdge::Right, 0.75, 0.0, 0.5);
+        // Still being pushed into the surface for the second tick
+        let two_ticks = Velocity {
+            x: velocity.x,
+            y: half_tick.y,
+        }
+        .after_contact(BoxEdge::Right, 0.75, 0.0, 0.5);
+        assert_eq!(one_tick.y, 0.5);
+        assert_eq!(two_ticks.y, 0.5);
+    }
+}
@@ -6,8 +6,12 @@ use serde::{Deserialize, Serialize};
 use shapes::Point;
 pub use uuid::Uuid;
 
+/// Surface properties used in collision resolution
+pub mod material;
 /// Stuff for rectangular physics items
 pub mod physbox;
+/// Deterministic fingerprints of game state
+pub mod state_hash;
 
 lazy_static! {
     /// Pre-calculate the square root of 2
@@ -136,6 +140,40 @@ impl Velocity {
         }
     }
 
+    /// The velocity after touching a surface on the given edge for `dt` seconds.
+    /// Only changes when moving into the edge: the component into the edge is reflected and scaled by
+    /// `restitution` (0 restitution stops it), and the component along the edge loses the `friction`
+    /// fraction of itself per second of contact, so it's reduced the same no matter the tick rate
+    pub fn after_contact(
+        self,
+        edge: BoxEdge,
+        friction: Scalar,
+        restitution: Scalar,
+        dt: Scalar,
+    ) -> Velocity {
+        if !self.is_reduced_by_edge(edge) {
+            return self;
+        }
+
+        let (normal, tangent) = match edge.perpendicular_axis() {
+            Axis::X => (self.x, self.y),
+            Axis::Y => (self.y, self.x),
+        };
+        let normal = -normal * restitution;
+        let tangent = tangent * (1.0 - friction).clamp(0.0, 1.0).powf(dt);
+
+        match edge.perpendicular_axis() {
+            Axis::X => Self {
+                x: normal,
+                y: tangent,
+            },
+            Axis::Y => Self {
+                x: tangent,
+                y: normal,
+            },
+        }
+    }
+
     /// Gives the angle of the velocity vector in radians
     /// with positive radians being from +x to +y. This would be clockwise on
     /// the display because +y is down.
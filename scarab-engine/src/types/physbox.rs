@@ -78,6 +78,14 @@ impl PhysBox {
         self.pos.x + self.size.w
     }
 
+    /// Gets the point at the middle of the box
+    pub fn center(&self) -> Point {
+        Point {
+            x: self.pos.x + self.size.w / 2.0,
+            y: self.pos.y + self.size.h / 2.0,
+        }
+    }
+
     /// Translates self so that the y value of the top edge is 'val'
     pub fn set_top_y(&mut self, val: Scalar) {
         self.pos.y = val;
@@ -152,21 +160,22 @@ impl PhysBox {
 
     /// Moves `self` so it does not overlap with `other`.
     /// Does nothing if they already don't overlap.
+    /// Returns the edge of `self` that was moved to touch `other`, if it moved
     /// ```
-    /// use scarab_engine::types::physbox::PhysBox;
+    /// use scarab_engine::types::{physbox::PhysBox, BoxEdge};
     ///
     /// let box1 = PhysBox::new([0.0, 0.0, 5.0, 5.0].into()).unwrap();
     /// let mut box2 = PhysBox::new([2.0, 4.0, 5.0, 5.0].into()).unwrap();
     ///
     /// assert!(box1.has_overlap(&box2));
     ///
-    /// box2.shift_to_nonoverlapping(&box1);
+    /// assert_eq!(box2.shift_to_nonoverlapping(&box1), Some(BoxEdge::Top));
     /// // Even though box1 and box2 still touch on box1's right axis they don't overlap
     /// assert_eq!(box2, PhysBox::new([2.0, 5.0, 5.0, 5.0].into()).unwrap());
     /// ```
-    pub fn shift_to_nonoverlapping(&mut self, other: &Self) {
+    pub fn shift_to_nonoverlapping(&mut self, other: &Self) -> Option<BoxEdge> {
         if !self.has_overlap(other) {
-            return;
+            return None;
         }
         let diffs = vec![
             (BoxEdge::Top, other.bottom_y() - self.top_y()),
@@ -185,9 +194,10 @@ impl PhysBox {
             }
         });
 
-        if let Some((edge, _diff)) = shift_edge_opt {
-            self.set_touching_opposite_edge(other, *edge)
-        }
+        shift_edge_opt.map(|(edge, _diff)| {
+            self.set_touching_opposite_edge(other, *edge);
+            *edge
+        })
     }
 
     /// Is the pos contained in the box
@@ -235,6 +245,40 @@ impl PhysBox {
             && (other.pos.y < this_bottom_right.y)
     }
 
+    /// Whether `self` and `other` overlap or share part of an edge
+    pub fn is_touching(&self, other: &Self) -> bool {
+        other.left_x() <= self.right_x()
+            && self.left_x() <= other.right_x()
+            && other.top_y() <= self.bottom_y()
+            && self.top_y() <= other.bottom_y()
+    }
+
+    /// Does the line segment from `from` to `to` pass through the inside of the box.
+    /// Like [PhysBox::has_overlap], a segment that only runs along an edge doesn't count
+    pub fn intersects_segment(&self, from: Point, to: Point) -> bool {
+        let mut t_enter: Scalar = 0.0;
+        let mut t_exit: Scalar = 1.0;
+        let axes = [
+            (from.x, to.x - from.x, self.left_x(), self.right_x()),
+            (from.y, to.y - from.y, self.top_y(), self.bottom_y()),
+        ];
+
+        for (start, delta, min, max) in axes {
+            if delta == 0.0 {
+                if start <= min || start >= max {
+                    return false;
+                }
+            } else {
+                let t_min = (min - start) / delta;
+                let t_max = (max - start) / delta;
+                t_enter = t_enter.max(t_min.min(t_max));
+                t_exit = t_exit.min(t_min.max(t_max));
+            }
+        }
+
+        t_enter < t_exit
+    }
+
     /// Is `self` fully contained within `other`
     /// Uses fully inclusive logic so that a.is_fully_contained_by(&a) is true
     /// i.e. in set notation `a.is_fully_contained_by(&b)` means that $a \subset b$
@@ -312,6 +356,12 @@ impl HasBoxMut for PhysBox {
 pub trait HasBox {
     /// A reference to the game object's PhysBox
     fn get_box(&self) -> &PhysBox;
+
+    /// The PhysBox to draw the game object at. This can differ from [HasBox::get_box]
+    /// when the game object's position is interpolated between updates
+    fn get_render_box(&self) -> PhysBox {
+        *self.get_box()
+    }
 }
 
 /// A trait for game objects that wrap a mutable physbox
@@ -508,4 +558,25 @@ mod test {
         assert!(physbox3.is_fully_contained_by(&physbox1));
         assert!(physbox4.is_fully_contained_by(&physbox1));
     }
+
+    #[test]
+    fn segment_intersection() {
+        let physbox = PhysBox::new([0.0, 0.0, 10.0, 10.0]).unwrap();
+        let segment =
+            |from: [f64; 2], to: [f64; 2]| physbox.intersects_segment(from.into(), to.into());
+
+        // Passing all the way through
+        assert!(segment([-5.0, 5.0], [15.0, 5.0]));
+        assert!(segment([-5.0, -5.0], [15.0, 15.0]));
+        // Starting or ending inside
+        assert!(segment([5.0, 5.0], [20.0, 20.0]));
+        assert!(segment([6.0, 4.0], [5.0, 5.0]));
+        // Stopping short or passing by
+        assert!(!segment([-5.0, 5.0], [-1.0, 5.0]));
+        assert!(!segment([-5.0, 12.0], [15.0, 12.0]));
+        assert!(!segment([-5.0, 8.0], [8.0, 25.0]));
+        // Running along an edge
+        assert!(!segment([-5.0, 0.0], [15.0, 0.0]));
+        assert!(!segment([10.0, -5.0], [10.0, 15.0]));
+    }
 }
@@ -0,0 +1,128 @@
+use core::hash::Hasher;
+
+use graphics::types::Scalar;
+
+use super::{material::PhysicsMaterial, physbox::PhysBox, Health, Solidity, Velocity};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Clone)]
+/// A 64-bit FNV-1a [Hasher] for fingerprinting game state.
+/// Unlike the standard library's hashers, its output only depends on the bytes written to it,
+/// so it's the same across runs, platforms and compiler versions
+pub struct StateHasher {
+    hash: u64,
+}
+
+impl StateHasher {
+    /// Writes a scalar by its bit pattern. Negative zero is written as zero so that
+    /// states that compare equal also hash equal
+    pub fn write_scalar(&mut self, scalar: Scalar) {
+        let scalar = if scalar == 0.0 { 0.0 } else { scalar };
+        self.write_u64(scalar.to_bits());
+    }
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self {
+            hash: FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    // Integers are always written little-endian so the hash doesn't depend on the platform
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// A trait for game state that contributes to a scene's state hash.
+/// Only state that affects the simulation should be hashed, not render-only state
+pub trait StateHash {
+    /// Writes the game object's simulation state to the hasher
+    fn hash_state(&self, hasher: &mut StateHasher);
+}
+
+impl StateHash for PhysBox {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_scalar(self.pos().x);
+        hasher.write_scalar(self.pos().y);
+        hasher.write_scalar(self.size().w);
+        hasher.write_scalar(self.size().h);
+    }
+}
+
+impl StateHash for Velocity {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_scalar(self.x);
+        hasher.write_scalar(self.y);
+    }
+}
+
+impl StateHash for Health {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_scalar(self.current());
+        hasher.write_scalar(self.max());
+    }
+}
+
+impl StateHash for Solidity {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_u8(self.0);
+    }
+}
+
+impl StateHash for PhysicsMaterial {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_scalar(self.friction);
+        hasher.write_scalar(self.restitution);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        let hasher = StateHasher::default();
+        assert_eq!(hasher.finish(), 0xcbf2_9ce4_8422_2325);
+
+        let mut hasher = StateHasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn negative_zero_hashes_as_zero() {
+        let mut positive = StateHasher::default();
+        Velocity { x: 0.0, y: 1.0 }.hash_state(&mut positive);
+
+        let mut negative = StateHasher::default();
+        Velocity { x: -0.0, y: 1.0 }.hash_state(&mut negative);
+
+        assert_eq!(positive.finish(), negative.finish());
+    }
+}
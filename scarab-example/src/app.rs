@@ -7,16 +7,22 @@ use piston::window::WindowSettings;
 use piston::{CloseArgs, EventSettings, Events, Input, ResizeArgs};
 use scarab_engine::{
     gameobject::{
-        entity::registry::{RegisteredDebugEntity, RegisteredEntity},
+        entity::{
+            registry::{RegisteredDebugEntity, RegisteredEntity},
+            HasEntity,
+        },
         field::Field,
     },
     input::InputRegistry,
     rendering::{
+        anchor::Anchor,
+        components::progress_bar::AnchoredProgressBar,
         debug::DebugView,
         registry::{TextureList, TextureRegistry},
         Camera, View,
     },
     scene::Scene,
+    types::HasHealth,
     App, ScarabError, ScarabResult,
 };
 use serde::de::DeserializeOwned;
@@ -27,12 +33,25 @@ use crate::{debug::DebugOptions, external_serde::EventSettingsDef};
 /// A semver-like version of the AppData's save format
 static SAVE_VERSION: &'static str = "0.1.0";
 
+/// The player's health shown in the top left corner of the screen
+fn player_health_bar() -> AnchoredProgressBar {
+    AnchoredProgressBar {
+        anchor: Anchor::TopLeft,
+        size: [30.0, 4.0],
+        offset: [2.0, 2.0],
+        border_size: 1.0,
+        background_color: [0.0, 0.0, 0.0, 1.0],
+        fill_color: [1.0, 0.0, 0.0, 1.0],
+    }
+}
+
 pub struct ExampleApp<E, V, I, J, D> {
     gl: GlGraphics, // OpenGL drawing backend.
     window: Window,
     data: AppData<E, V, I, J, D>,
     save_name: String,
     texture_registry: TextureRegistry,
+    health_bar: AnchoredProgressBar,
 }
 
 impl<E, V, I, J, D> ExampleApp<E, V, I, J, D> {
@@ -63,6 +82,7 @@ impl<E, V, I, J, D> ExampleApp<E, V, I, J, D> {
             },
             save_name,
             texture_registry,
+            health_bar: player_health_bar(),
         })
     }
 }
@@ -106,6 +126,7 @@ where
             data: app_data,
             save_name,
             texture_registry,
+            health_bar: player_health_bar(),
         })
     }
 }
@@ -125,7 +146,6 @@ where
     fn render(&mut self, args: &RenderArgs) {
         use graphics::*;
 
-        const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
         const GREY: [f32; 4] = [0.4, 0.4, 0.4, 1.0];
 
         self.gl.draw(args.viewport(), |ctx, gl| {
@@ -143,11 +163,23 @@ where
                     gl,
                 )
                 .unwrap();
-            self.data.camera.render_gutters(BLACK, args, ctx, gl);
+            self.data
+                .camera
+                .render_gutter_fill(args, ctx, &self.texture_registry, gl);
+            if let Some(player) = self.data.scene.player() {
+                self.health_bar.render(
+                    player.get_entity().get_health().fraction(),
+                    &self.data.camera,
+                    ctx,
+                    gl,
+                );
+            }
+            self.data.camera.render_color_grade(args, ctx, gl);
         });
     }
 
     fn update(&mut self, args: &UpdateArgs) {
+        self.data.camera.color_grading_mut().update(args.dt);
         let _ = self
             .data
             .scene
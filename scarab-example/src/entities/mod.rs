@@ -1,4 +1,5 @@
 use core::marker::PhantomData;
+use std::path::Path;
 
 use derivative::Derivative;
 use graphics::{types::Color, Context};
@@ -18,7 +19,10 @@ use scarab_engine::{
         Camera, View,
     },
     scene::GameTickArgs,
-    types::{physbox::HasBox, HasHealth},
+    types::{
+        physbox::{HasBox, HasBoxMut, PhysBox},
+        HasHealth,
+    },
     ScarabResult,
 };
 use serde::{Deserialize, Serialize};
@@ -83,6 +87,30 @@ impl RegisteredEntity for ExampleEntities {
         }
     }
 
+    fn batch_texture(&self) -> Option<&Path> {
+        match self {
+            Self::Player((_player, view)) => view.standard_view().texture_path(),
+            Self::Enemy((_enemy, view)) => view.standard_view().texture_path(),
+        }
+    }
+
+    fn draw_bounds(&self) -> Option<PhysBox> {
+        match self {
+            Self::Player((player, view)) => {
+                view.standard_view().draw_bounds(&player.get_render_box())
+            }
+            Self::Enemy((enemy, view)) => view.standard_view().draw_bounds(&enemy.get_render_box()),
+        }
+    }
+
+    fn fit_box_to_sprite(&mut self) -> ScarabResult<()> {
+        match self {
+            Self::Player((player, view)) => view.standard_view().fit_box(player.get_box_mut())?,
+            Self::Enemy((enemy, view)) => view.standard_view().fit_box(enemy.get_box_mut())?,
+        }
+        Ok(())
+    }
+
     fn maybe_player(&self) -> Option<&<ExampleEntities as RegisteredEntity>::Player> {
         match self {
             Self::Player((p, _)) => Some(p),
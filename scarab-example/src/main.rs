@@ -84,6 +84,8 @@ fn main() -> ScarabResult<()> {
         field,
         StandardAndDebugView::from((field_view, FieldDebug {})),
     );
+    // Draw entities lower on the screen over the ones behind them
+    scene.set_y_sort(true);
 
     // Create a camera with a 100x100 tile view
     let cambox = PhysBox::new([0.0, 0.0, camera_size[0].into(), camera_size[1].into()])?;
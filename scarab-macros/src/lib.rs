@@ -49,6 +49,16 @@ pub fn derive_has_box(input: TokenStream) -> TokenStream {
         Err(e) => return e.into_compile_error().into(),
     };
 
+    let render_implementation;
+    match impl_call_fn_on_marked_field(
+        &input,
+        Ident::new("get_render_box", Span::call_site()),
+        "has_box",
+    ) {
+        Ok(i) => render_implementation = i,
+        Err(e) => return e.into_compile_error().into(),
+    };
+
     let name = input.ident;
     let root = scarab_root();
 
@@ -59,6 +69,10 @@ pub fn derive_has_box(input: TokenStream) -> TokenStream {
             fn get_box(&self) -> &#root ::types::physbox::PhysBox {
                 #implementation
             }
+
+            fn get_render_box(&self) -> #root ::types::physbox::PhysBox {
+                #render_implementation
+            }
         }
     };
 